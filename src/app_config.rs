@@ -0,0 +1,287 @@
+use std::{collections::HashSet, fs, path::{Path, PathBuf}};
+
+use anyhow::anyhow;
+use colored::*;
+
+use img_dumper::{bundle::BUNDLE_PAGE_NAME, history::find_latest_snapshot, scan_diff::Snapshot, scanner::{DEFAULT_EXTENSIONS, DEFAULT_IGNORED_DIR_NAMES}, special_files::parse_css_file,
+    utils::{convert_to_absolute, join_paths}, OutputFormat, ParsableFileSpec, Scanner};
+
+use crate::{cli::{CommandLineArgs, SubCommand}, config_file::{self, ConfigFile}};
+
+#[derive(Debug)]
+pub struct AppConfig {
+    pub command_line_args: CommandLineArgs,
+
+    // path of the img-dumper.toml that was loaded, if one was found
+    pub config_file_path: Option<String>,
+
+    // full path of generated file, `-` when it is written to stdout
+    pub output_file_path : String,
+
+    // root directory, extensions, ignored folders and special files (sp-icons and font-awesome css files) to scan
+    pub scanner: Scanner,
+
+    // absolute path of the directory that a snapshot of every scan is saved in
+    pub history_dir: Option<String>,
+
+    // absolute path of the directory of the bundle, that `output_file_path` is the index.html of
+    pub bundle_dir: Option<String>,
+
+    // the latest snapshot of the history directory, before this run
+    pub previous_snapshot: Option<Snapshot>,
+}
+
+impl AppConfig {
+    pub fn is_output_stdout(&self) -> bool {
+        self.output_file_path == "-"
+    }
+
+    pub fn init(args: CommandLineArgs) -> anyhow::Result<Self> {
+        let root_dir = {
+            if let Some(dir) = &args.dir {
+                dir.clone()
+            } else if let Some(x) = get_htdocs_path() {
+                x
+            } else if args.command == SubCommand::Diff && args.diff_paths.len() == 2 {
+                // nothing is scanned, it is only where the config file is searched for
+                ".".to_owned()
+            } else {
+                return Err(anyhow!("Unable to find htdocs folder and no custom root directory provided (--dir <path>)".red()));
+            }
+        };
+        let root_dir = convert_to_absolute(&root_dir);
+
+        if !Path::new(&root_dir).exists() {
+            return Err(anyhow!(format!("The (root) directory '{}' does not exist", root_dir).red()));
+        }
+
+        let (config_file_path, config_file) = match ConfigFile::find_and_load(&root_dir)? {
+            Some((path, config_file)) => (Some(path), config_file),
+            None => (None, ConfigFile::default()),
+        };
+
+        let mut output_file_name =
+            if let Some(name) = args.name.as_ref().or(config_file.output.name.as_ref()) {
+                name.to_owned()
+            } else {
+                match args.command {
+                    SubCommand::Diff => "icons_diff_generated".to_owned(),
+                    _ => "icons_report_generated".to_owned(),
+                }
+            };
+        output_file_name.push('.');
+        output_file_name.push_str(args.format.get_extension());
+
+        let output_file_path =
+            if let Some(target) = args.target.as_ref().or(config_file.output.target.as_ref()) {
+                let path = PathBuf::from(target);
+                if !path.is_dir() {
+                    return Err(anyhow!(format!("The target '{}' is not a directory", target).red()));
+                }
+                path
+            } else {
+                let desktop_dir = dirs::desktop_dir();
+                if let Some(dir) = desktop_dir {
+                    dir
+                } else {
+                    Path::new(".").to_path_buf()
+                }
+            };
+        let bundle_dir = args.bundle_dir.as_deref().map(convert_to_absolute);
+        let output_file_path = match (args.output.as_deref(), &bundle_dir) {
+            (Some(_), Some(_)) => return Err(anyhow!("--bundle can't be combined with --output".red())),
+            (None, Some(dir)) => join_paths(dir, BUNDLE_PAGE_NAME, "/"),
+            (Some("-"), None) => "-".to_owned(),
+            (Some(output), None) => {
+                let output_path = Path::new(output);
+                if output_path.is_dir() {
+                    return Err(anyhow!(format!("The output '{}' is a directory, it should be a file path", output).red()));
+                }
+                convert_to_absolute(output)
+            }
+            (None, None) => convert_to_absolute(&join_paths(&output_file_path.to_string_lossy(), &output_file_name, "/")),
+        };
+
+        if args.is_portable && (args.command != SubCommand::Scan || args.format != OutputFormat::Html) {
+            return Err(anyhow!("--portable can only be used for the html report of the `scan` command".red()));
+        }
+
+        if let Some(dir) = &bundle_dir {
+            if args.command != SubCommand::Scan || args.format != OutputFormat::Html || args.is_portable {
+                return Err(anyhow!("--bundle can only be used for the html report of the `scan` command, without --portable".red()));
+            }
+            fs::create_dir_all(dir).map_err(|e| anyhow!(format!("Unable to create the bundle directory '{}': {}", dir, e).red()))?;
+        }
+
+        let history_dir = args.history_dir.as_deref().map(convert_to_absolute);
+        if history_dir.is_some() && !matches!(args.command, SubCommand::Scan | SubCommand::Serve) {
+            return Err(anyhow!(format!("--history can't be used with the `{}` command", args.command.get_name()).red()));
+        }
+        let previous_snapshot = match &history_dir {
+            Some(dir) => {
+                fs::create_dir_all(dir).map_err(|e| anyhow!(format!("Unable to create the history directory '{}': {}", dir, e).red()))?;
+                find_latest_snapshot(dir)?.map(|path| Snapshot::load(&path)).transpose()?
+            }
+            None => None,
+        };
+
+        if args.command == SubCommand::Diff && args.format != OutputFormat::Html {
+            return Err(anyhow!("The `diff` command only generates html".red()));
+        }
+
+        // serving always watches
+        if args.is_watching && !matches!(args.command, SubCommand::Scan | SubCommand::Serve) {
+            return Err(anyhow!(format!("--watch can't be used with the `{}` command", args.command.get_name()).red()));
+        }
+        if args.is_watching && output_file_path == "-" {
+            return Err(anyhow!("--watch can't be used with `--output -`".red()));
+        }
+
+        let relevant_extensions = if !args.extensions.is_empty() {
+            args.extensions.clone()
+        } else {
+            let mut relevant_extensions = config_file.scan.extensions.unwrap_or_else(||
+                DEFAULT_EXTENSIONS.iter().map(|ext| ext.to_string()).collect());
+            relevant_extensions.extend(config_file.scan.extra_extensions);
+            relevant_extensions
+        };
+
+        let mut irrelevant_dir_names = config_file.scan.ignored_dirs.unwrap_or_else(||
+            DEFAULT_IGNORED_DIR_NAMES.iter().map(|dir_name| dir_name.to_string()).collect());
+        irrelevant_dir_names.extend(config_file.scan.extra_ignored_dirs);
+        irrelevant_dir_names.extend(args.excluded_dir_names.iter().cloned());
+
+        let mut scanner = Scanner::new(root_dir.clone())
+            .extensions(relevant_extensions)
+            .ignored_dir_names(irrelevant_dir_names);
+
+        // the duplicates don't need them, and reading every source file is not free
+        if args.command != SubCommand::Dupes {
+            scanner = scanner.find_references();
+        }
+
+        if let Some(threshold) = args.similarity_threshold {
+            scanner = scanner.find_similar(threshold);
+        }
+
+        if !args.is_cache_disabled {
+            if let Some(cache_file_path) = get_cache_file_path(&root_dir) {
+                scanner = scanner.cache_file(cache_file_path);
+            }
+        }
+
+        if !args.is_basic {
+            scanner = scanner
+                .special_file(special_file_spec_from_config("sp-icons", "style",
+                    "mega-commons-angular-js/assets/fonts/sp-icons",
+                    None, 15000, &config_file.special_files.sp_icons, &root_dir))
+                .special_file(special_file_spec_from_config("font-awesome", "font-awesome",
+                    "mega-commons-angular-js/bower_components/components-font-awesome/css",
+                    Some("/bower_components/components-font-awesome/css"), 38000, &config_file.special_files.font_awesome, &root_dir)
+                    .html_classes("fa", "fa-"));
+            for section in &config_file.icon_sets {
+                scanner = scanner.special_file(icon_set_spec_from_config(section, &root_dir)?);
+            }
+            let mut titles = HashSet::new();
+            if let Some(file_spec) = scanner.get_special_file_specs().iter().find(|file_spec| !titles.insert(file_spec.title.as_str())) {
+                return Err(anyhow!(format!("There is more than one icon set with the title '{}'", file_spec.title).red()));
+            }
+        }
+
+        Ok (Self {
+            command_line_args: args,
+            config_file_path,
+            output_file_path,
+            scanner,
+            history_dir,
+            bundle_dir,
+            previous_snapshot,
+        })
+    }
+}
+
+// Builds the spec from the built-in defaults, letting the config file override each location independently.
+fn special_file_spec_from_config(title: &str, default_name: &str, default_known_dir: &str, default_relative_dir: Option<&str>,
+        approximate_size_bytes: usize, section: &config_file::SpecialFileSection, root_dir: &str) -> ParsableFileSpec {
+    let name = section.name.as_deref().unwrap_or(default_name);
+    let extension = section.extension.as_deref().unwrap_or("css");
+    let known_abs_dir = match &section.known_dir {
+        Some(dir) if Path::new(dir).is_absolute() => dir.replace('\\', "/"),
+        Some(dir) => join_paths(root_dir, dir.trim_start_matches(['/', '\\']), "/"),
+        None => join_paths(root_dir, default_known_dir, "/"),
+    };
+    let relative_dir = match &section.relative_dir {
+        Some(dir) if dir.trim().is_empty() => None,
+        Some(dir) => Some(format!("/{}", dir.trim_start_matches(['/', '\\']).replace('\\', "/"))),
+        None => default_relative_dir.map(|dir| dir.to_owned()),
+    };
+
+    ParsableFileSpec::new(title, name, extension, Some(known_abs_dir), relative_dir, approximate_size_bytes, parse_css_file)
+}
+
+// Builds the spec of an icon set that was declared in the config file, from its `path`, its `project_path` or both
+fn icon_set_spec_from_config(section: &config_file::IconSetSection, root_dir: &str) -> anyhow::Result<ParsableFileSpec> {
+    let known_abs_path = match &section.path {
+        Some(path) if Path::new(path).is_absolute() => Some(path.replace('\\', "/")),
+        Some(path) => Some(join_paths(root_dir, &path.trim_start_matches(['/', '\\']).replace('\\', "/"), "/")),
+        None => None,
+    };
+    let project_path = section.project_path.as_ref().map(|path| format!("/{}", path.trim_start_matches(['/', '\\']).replace('\\', "/")));
+
+    let file_names: Vec<&str> = known_abs_path.iter().chain(&project_path)
+        .map(|path| path.rsplit('/').next().unwrap_or(path))
+        .collect();
+    let file_name = match file_names.as_slice() {
+        [] => return Err(anyhow!(format!("The icon set '{}' of the config file needs a `path` or a `project_path`", section.title).red())),
+        [file_name, other_file_name] if file_name != other_file_name =>
+            return Err(anyhow!(format!("The `path` and the `project_path` of the icon set '{}' should have the same file name", section.title).red())),
+        [file_name, ..] => *file_name,
+    };
+    let Some((name, extension)) = file_name.rsplit_once('.') else {
+        return Err(anyhow!(format!("The file of the icon set '{}' should have an extension, like `.css`", section.title).red()));
+    };
+    let get_dir = |path: &String| path[..path.len() - file_name.len()].trim_end_matches('/').to_owned();
+    let base_class = section.base_class.as_deref().unwrap_or(section.class_prefix.trim_end_matches('-'));
+
+    Ok(ParsableFileSpec::new(&section.title, name, extension, known_abs_path.as_ref().map(get_dir), project_path.as_ref().map(get_dir),
+        40000, parse_css_file)
+        .html_classes(base_class, &section.class_prefix))
+}
+
+// One cache file per root directory, inside the user cache directory, so that it never ends up in a project
+fn get_cache_file_path(root_dir: &str) -> Option<String> {
+    let root_dir_hash = blake3::hash(root_dir.as_bytes()).to_hex();
+    dirs::cache_dir().map(|dir| dir.join("img-dumper").join(format!("scan-{}.json", &root_dir_hash[..16])).to_string_lossy().replace('\\', "/"))
+}
+
+fn get_htdocs_path() -> Option<String> {
+    let os = std::env::consts::OS;
+
+    match os {
+        "windows" => {
+            let default_path_str = "C:/xampp/htdocs";
+            let xampp_htdocs_path = PathBuf::from(default_path_str);
+            if xampp_htdocs_path.exists() {
+                return Some(default_path_str.to_owned());
+            }
+        }
+        "macos" => {
+            let default_path_str = "/Applications/XAMPP/xamppfiles/htdocs";
+            let xampp_htdocs_path = PathBuf::from(default_path_str);
+            if xampp_htdocs_path.exists() {
+                return Some(default_path_str.to_owned());
+            }
+        }
+        "linux" => {
+            for path_str in ["/opt/lampp/htdocs", "/var/www/html"] {
+                let xampp_htdocs_path = PathBuf::from(path_str);
+                if xampp_htdocs_path.exists() {
+                    return Some(path_str.to_owned());
+                }
+            }
+        }
+        _ => {return None}
+    }
+
+    None
+}
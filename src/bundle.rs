@@ -0,0 +1,99 @@
+use std::{collections::{BTreeMap, HashSet}, fs, path::Path};
+
+use anyhow::Context;
+use colored::*;
+use rayon::prelude::*;
+
+use crate::{html_report::generate_bundled_html_page_as_string, portable::get_css_file_paths, utils::join_paths, ScanResult};
+
+pub const BUNDLE_PAGE_NAME: &str = "index.html";
+const ASSETS_DIR_NAME: &str = "assets";
+
+// Where the files that the page shows are copied to, relative to the bundle directory.
+// The images keep the layout of their project under `assets/images/<project>/`, the fonts of the icon sets go to `assets/fonts/`.
+#[derive(Debug, Default)]
+pub struct BundleAssets {
+    // source path -> path of its copy
+    pub paths: BTreeMap<String, String>,
+    // the paths of the copies in lowercase, since the bundle may be published from a case insensitive file system
+    taken_paths: HashSet<String>,
+}
+
+impl BundleAssets {
+    pub fn plan(scan_result: &ScanResult) -> Self {
+        let mut assets = Self::default();
+        for project_dir in scan_result.projects.values() {
+            for img in &project_dir.images {
+                let path_in_project = Path::new(&img.path).strip_prefix(&project_dir.path)
+                    .map(|path| path.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_else(|_| img.name.clone());
+                assets.add(&img.path, &format!("{}/images/{}/{}", ASSETS_DIR_NAME, project_dir.name, path_in_project));
+            }
+        }
+        // the fonts that don't exist are left out, so that the css keeps pointing at where they were expected
+        for icon_set in &scan_result.icon_sets {
+            for path in get_css_file_paths(&icon_set.css) {
+                let file_name = Path::new(&path).file_name().map(|name| name.to_string_lossy().into_owned());
+                if let Some(file_name) = file_name.filter(|_| Path::new(&path).is_file()) {
+                    assets.add(&path, &format!("{}/fonts/{}", ASSETS_DIR_NAME, file_name));
+                }
+            }
+        }
+
+        assets
+    }
+
+    // Gives the file `wanted_path`, unless another file has it already. Then `-2`, `-3`... is appended to its stem,
+    // like `assets/fonts/icons-2.woff` for the font of a second icon set with the same file name.
+    fn add(&mut self, source_path: &str, wanted_path: &str) {
+        if self.paths.contains_key(source_path) {
+            return;
+        }
+        let file_name_start = wanted_path.rfind('/').map_or(0, |index| index + 1);
+        let (stem, extension) = match wanted_path[file_name_start..].rfind('.').filter(|&index| index > 0) {
+            Some(index) => wanted_path.split_at(file_name_start + index),
+            None => (wanted_path, ""),
+        };
+        let mut path = wanted_path.to_owned();
+        let mut suffix = 1;
+        while !self.taken_paths.insert(path.to_lowercase()) {
+            suffix += 1;
+            path = format!("{}-{}{}", stem, suffix, extension);
+        }
+        self.paths.insert(source_path.to_owned(), path);
+    }
+}
+
+// Writes the page to `bundle_dir/index.html` and copies the images and the fonts it shows to `bundle_dir/assets/`,
+// with relative links between them, so that the directory can be published as it is. Returns the path of the page.
+// The copies that are at least as recent as their source and have the same size are kept, which makes rewriting
+// the bundle while watching cheap. Nothing is deleted, so the files that disappeared from the scan stay in `assets/`.
+pub fn write_bundle(scan_result: &ScanResult, bundle_dir: &str) -> anyhow::Result<String> {
+    let assets = BundleAssets::plan(scan_result);
+    assets.paths.par_iter().try_for_each(|(source_path, relative_path)| copy_asset(source_path, &join_paths(bundle_dir, relative_path, "/")))?;
+
+    let page_path = join_paths(bundle_dir, BUNDLE_PAGE_NAME, "/");
+    let html = generate_bundled_html_page_as_string(scan_result, &assets.paths)?;
+    fs::write(&page_path, html).context(format!("Failed to write `{}`", page_path).red())?;
+
+    Ok(page_path)
+}
+
+fn copy_asset(source_path: &str, target_path: &str) -> anyhow::Result<()> {
+    if is_up_to_date(source_path, target_path) {
+        return Ok(());
+    }
+    if let Some(dir) = Path::new(target_path).parent() {
+        fs::create_dir_all(dir).context(format!("Failed to create the directory `{}`", dir.display()).red())?;
+    }
+    fs::copy(source_path, target_path).context(format!("Failed to copy `{}` to `{}`", source_path, target_path).red())?;
+    Ok(())
+}
+
+fn is_up_to_date(source_path: &str, target_path: &str) -> bool {
+    let (Ok(source), Ok(target)) = (fs::metadata(source_path), fs::metadata(target_path)) else { return false };
+    match (source.modified(), target.modified()) {
+        (Ok(source_modified), Ok(target_modified)) => source.len() == target.len() && target_modified >= source_modified,
+        _ => false,
+    }
+}
@@ -0,0 +1,251 @@
+use clap::{error::ErrorKind, Arg, ArgAction, ArgMatches, Command};
+
+use img_dumper::{similarity::DEFAULT_SIMILARITY_THRESHOLD, OutputFormat};
+
+use crate::message_printer::{self, Argument};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SubCommand {
+    // scans the root directory and generates the report, this is what runs when no subcommand is given
+    #[default]
+    Scan,
+    // prints the groups of identical images, without generating a report
+    Dupes,
+    // prints the images that no source file points at, without generating a report
+    Unused,
+    // prints the references of the source files to images that don't exist, without generating a report
+    Broken,
+    // serves the report and the images from a local http server, that reloads the page after every rescan
+    Serve,
+    // compares two json exports, or one json export with the current tree
+    Diff,
+}
+
+impl SubCommand {
+    pub const ALL: &'static [SubCommand] = &[SubCommand::Scan, SubCommand::Dupes, SubCommand::Unused, SubCommand::Broken, SubCommand::Serve,
+        SubCommand::Diff];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SubCommand::Scan => "scan",
+            SubCommand::Dupes => "dupes",
+            SubCommand::Unused => "unused",
+            SubCommand::Broken => "broken",
+            SubCommand::Serve => "serve",
+            SubCommand::Diff => "diff",
+        }
+    }
+
+    pub fn get_help_msg(&self) -> &'static str {
+        match self {
+            SubCommand::Scan => "Scan the root directory and generate the report (default)",
+            SubCommand::Dupes => "Print the groups of identical images across projects, with the bytes they waste",
+            SubCommand::Unused => "Print the images that no source file of their project points at, with the bytes they take",
+            SubCommand::Broken => "Print the references of source files to images that don't exist, as file:line",
+            SubCommand::Serve => "Serve the report from a local server, that reloads it whenever a project changes",
+            SubCommand::Diff => "Compare two json exports (or one with the current tree) and generate an html diff",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|sub_command| sub_command.get_name() == name)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CommandLineArgs {
+    pub command: SubCommand,
+    pub dir: Option<String>,
+    pub target: Option<String>,
+    pub name: Option<String>,
+    pub is_basic: bool,
+    // when not empty, replaces the extensions of the defaults and of the config file
+    pub extensions: Vec<String>,
+    // added on top of the ignored directory names of the defaults and of the config file
+    pub excluded_dir_names: Vec<String>,
+    pub format: OutputFormat,
+    // full path of the generated file, `-` means stdout
+    pub output: Option<String>,
+    // max hamming distance between perceptual hashes, None when similar images should not be searched for
+    pub similarity_threshold: Option<u32>,
+    // keep running and regenerate the report on changes
+    pub is_watching: bool,
+    // port of the local server, for the serve command
+    pub port: Option<u16>,
+    // read every image again, without the scan cache
+    pub is_cache_disabled: bool,
+    // number of worker threads, None for one per logical core
+    pub jobs: Option<usize>,
+    // embed the images and the fonts in the html report
+    pub is_portable: bool,
+    // directory that the report is written to with a copy of its images and fonts
+    pub bundle_dir: Option<String>,
+    // directory of the snapshots, that the new images are found with
+    pub history_dir: Option<String>,
+    // the json exports that the diff command compares, the old one first. When there is only one, it is compared with a scan.
+    pub diff_paths: Vec<String>,
+}
+
+// the positional arguments of the diff command
+const DIFF_PATHS_ARG: &str = "paths";
+
+fn value_arg(argument: Argument, value_name: &'static str) -> Arg {
+    Arg::new(argument.get_name())
+        .short(argument.get_short())
+        .long(argument.get_name())
+        .value_name(value_name)
+        .help(argument.get_help_msg())
+        .long_help(argument.get_long_help_msg())
+        .global(true)
+}
+
+fn repeatable_arg(argument: Argument, value_name: &'static str) -> Arg {
+    value_arg(argument, value_name)
+        .action(ArgAction::Append)
+        .value_delimiter(',')
+}
+
+fn flag_arg(argument: Argument, action: ArgAction) -> Arg {
+    Arg::new(argument.get_name())
+        .short(argument.get_short())
+        .long(argument.get_name())
+        .action(action)
+        .help(argument.get_help_msg())
+        .long_help(argument.get_long_help_msg())
+        .global(true)
+}
+
+pub fn build_command() -> Command {
+    let mut command = Command::new("img-dumper")
+        .about(message_printer::get_program_description())
+        .disable_help_flag(true)
+        .disable_help_subcommand(true)
+        .arg(value_arg(Argument::Dir, "PATH"))
+        .arg(value_arg(Argument::Target, "PATH"))
+        .arg(value_arg(Argument::Name, "NAME"))
+        .arg(flag_arg(Argument::Basic, ArgAction::SetTrue))
+        .arg(repeatable_arg(Argument::Ext, "EXT"))
+        .arg(repeatable_arg(Argument::Exclude, "DIR_NAME"))
+        .arg(value_arg(Argument::Format, "FORMAT")
+            .value_parser(OutputFormat::ALL.iter().map(|format| format.get_name()).collect::<Vec<_>>()))
+        .arg(value_arg(Argument::Output, "PATH").allow_hyphen_values(true))
+        .arg(value_arg(Argument::Similar, "THRESHOLD")
+            .num_args(0..=1)
+            .value_parser(clap::value_parser!(u32).range(0..=64)))
+        .arg(flag_arg(Argument::Watch, ArgAction::SetTrue))
+        .arg(value_arg(Argument::Port, "PORT").value_parser(clap::value_parser!(u16).range(1..)))
+        .arg(flag_arg(Argument::NoCache, ArgAction::SetTrue))
+        .arg(value_arg(Argument::History, "DIR"))
+        .arg(flag_arg(Argument::Portable, ArgAction::SetTrue))
+        .arg(value_arg(Argument::Bundle, "DIR"))
+        .arg(value_arg(Argument::Jobs, "N").value_parser(clap::value_parser!(u16).range(1..)))
+        .arg(flag_arg(Argument::Help, ArgAction::Help));
+
+    for sub_command in SubCommand::ALL {
+        let mut subcommand = Command::new(sub_command.get_name()).about(sub_command.get_help_msg());
+        if *sub_command == SubCommand::Diff {
+            subcommand = subcommand.arg(Arg::new(DIFF_PATHS_ARG)
+                .value_names(["OLD", "NEW"])
+                .num_args(1..=2)
+                .required(true)
+                .help("The json export of the old scan, and optionally of the new one. The current tree is scanned when NEW is not given"));
+        }
+        command = command.subcommand(subcommand);
+    }
+
+    command
+}
+
+// Returns None when the program should not continue, because only the help message was requested.
+pub fn parse_args() -> anyhow::Result<Option<CommandLineArgs>> {
+    let matches = match build_command().try_get_matches_from(join_split_values(std::env::args())) {
+        Ok(matches) => matches,
+        Err(e) if e.kind() == ErrorKind::DisplayHelp => {
+            e.print()?;
+            return Ok(None);
+        }
+        Err(e) => e.exit(),
+    };
+
+    let (command, matches) = match matches.subcommand() {
+        Some((name, sub_matches)) => (SubCommand::from_name(name).unwrap_or_default(), sub_matches),
+        None => (SubCommand::default(), &matches),
+    };
+
+    Ok(Some(CommandLineArgs {
+        command,
+        dir: get_path(matches, Argument::Dir),
+        target: get_path(matches, Argument::Target),
+        name: get_string(matches, Argument::Name),
+        is_basic: matches.get_flag(Argument::Basic.get_name()),
+        extensions: get_strings(matches, Argument::Ext),
+        excluded_dir_names: get_strings(matches, Argument::Exclude),
+        format: get_string(matches, Argument::Format).and_then(|name| OutputFormat::from_name(&name)).unwrap_or_default(),
+        output: get_path(matches, Argument::Output),
+        similarity_threshold: get_optional_value_flag(matches, Argument::Similar, DEFAULT_SIMILARITY_THRESHOLD),
+        is_watching: matches.get_flag(Argument::Watch.get_name()),
+        port: matches.get_one::<u16>(Argument::Port.get_name()).copied(),
+        is_cache_disabled: matches.get_flag(Argument::NoCache.get_name()),
+        jobs: matches.get_one::<u16>(Argument::Jobs.get_name()).map(|&jobs| jobs as usize),
+        is_portable: matches.get_flag(Argument::Portable.get_name()),
+        bundle_dir: get_path(matches, Argument::Bundle),
+        history_dir: get_path(matches, Argument::History),
+        diff_paths: match command {
+            SubCommand::Diff => matches.get_many::<String>(DIFF_PATHS_ARG)
+                .map(|paths| paths.map(|path| path.replace('\\', "/")).collect())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        },
+    }))
+}
+
+// The hand-rolled parser of the first versions read everything up to the next argument as the value, so scripts could pass
+// `--dir C:/My Projects/htdocs` without quotes. The pieces that the shell split such a path or name into are joined back
+// with a space, up to the next argument or subcommand. After a subcommand, which those scripts never had, nothing is joined.
+fn join_split_values(args: impl Iterator<Item = String>) -> Vec<String> {
+    let spaced_arguments = [Argument::Dir, Argument::Target, Argument::Name];
+    let takes_spaced_value = |arg: &str| spaced_arguments.iter().any(|argument| {
+        arg == format!("--{}", argument.get_name()) || argument.get_short().is_some_and(|short| arg == format!("-{}", short))
+    });
+    let is_sub_command = |arg: &str| SubCommand::from_name(arg).is_some();
+
+    let mut joined: Vec<String> = Vec::new();
+    let mut args = args.peekable();
+    let mut is_before_sub_command = true;
+    while let Some(arg) = args.next() {
+        is_before_sub_command &= !is_sub_command(&arg);
+        let is_spaced = is_before_sub_command && takes_spaced_value(&arg);
+        joined.push(arg);
+        if !is_spaced {
+            continue;
+        }
+        let Some(mut value) = args.next_if(|value| !value.starts_with('-')) else { continue };
+        while let Some(piece) = args.next_if(|piece| !piece.starts_with('-') && !is_sub_command(piece)) {
+            value.push(' ');
+            value.push_str(&piece);
+        }
+        joined.push(value);
+    }
+
+    joined
+}
+
+// for arguments with an optional value, None when the argument was not given at all
+fn get_optional_value_flag(matches: &ArgMatches, argument: Argument, default_value: u32) -> Option<u32> {
+    matches.value_source(argument.get_name())
+        .map(|_| matches.get_one::<u32>(argument.get_name()).copied().unwrap_or(default_value))
+}
+
+fn get_string(matches: &ArgMatches, argument: Argument) -> Option<String> {
+    matches.get_one::<String>(argument.get_name()).and_then(|value| img_dumper::utils::get_trimmed_if_not_empty(value))
+}
+
+fn get_path(matches: &ArgMatches, argument: Argument) -> Option<String> {
+    get_string(matches, argument).map(|path| path.replace('\\', "/"))
+}
+
+fn get_strings(matches: &ArgMatches, argument: Argument) -> Vec<String> {
+    matches.get_many::<String>(argument.get_name())
+        .map(|values| values.filter_map(|value| img_dumper::utils::get_trimmed_if_not_empty(value)).collect())
+        .unwrap_or_default()
+}
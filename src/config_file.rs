@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use colored::*;
+use serde::Deserialize;
+
+pub const CONFIG_FILE_NAME: &str = "img-dumper.toml";
+
+// Optional per-team configuration, looked up first inside the root directory and then inside the user config dir.
+// Every field is optional, anything that is not set keeps the built-in default.
+// Command line arguments always win over the values of this file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub output: OutputSection,
+    pub scan: ScanSection,
+    pub special_files: SpecialFilesSection,
+    // `[[icon_sets]]` tables, the icon font stylesheets that are parsed on top of the special files
+    pub icon_sets: Vec<IconSetSection>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputSection {
+    // the name of the generated file, without extension
+    pub name: Option<String>,
+    // the directory that the generated file will be saved
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanSection {
+    // replaces the default extensions entirely
+    pub extensions: Option<Vec<String>>,
+    // added on top of the (default or replaced) extensions
+    pub extra_extensions: Vec<String>,
+    // replaces the default ignored directory names entirely
+    pub ignored_dirs: Option<Vec<String>>,
+    // added on top of the (default or replaced) ignored directory names
+    pub extra_ignored_dirs: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpecialFilesSection {
+    pub font_awesome: SpecialFileSection,
+    pub sp_icons: SpecialFileSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpecialFileSection {
+    // the file name without extension
+    pub name: Option<String>,
+    pub extension: Option<String>,
+    // absolute, or relative to the root directory
+    pub known_dir: Option<String>,
+    // relative to a *project* (top level folder inside root dir), an empty string disables the per-project lookup
+    pub relative_dir: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IconSetSection {
+    // the title of its section in the report, and its id in the json export
+    pub title: String,
+    // absolute, or relative to the root directory
+    pub path: Option<String>,
+    // relative to a *project* (top level folder inside root dir), searched for when `path` is not given or doesn't exist
+    pub project_path: Option<String>,
+    // the prefix of the icon class names, like `mdi-`
+    pub class_prefix: String,
+    // the class that every icon needs, when the stylesheet doesn't declare one. Defaults to the class prefix without its trailing dash.
+    pub base_class: Option<String>,
+}
+
+impl ConfigFile {
+    // Returns the path of the config file that was used (if any) together with its parsed contents.
+    pub fn find_and_load(root_dir: &str) -> anyhow::Result<Option<(String, ConfigFile)>> {
+        let candidates = [
+            Some(PathBuf::from(root_dir).join(CONFIG_FILE_NAME)),
+            dirs::config_dir().map(|dir| dir.join("img-dumper").join(CONFIG_FILE_NAME)),
+        ];
+
+        for path in candidates.into_iter().flatten() {
+            if path.is_file() {
+                let config = Self::load(&path)?;
+                return Ok(Some((path.to_string_lossy().replace('\\', "/"), config)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<ConfigFile> {
+        let contents = std::fs::read_to_string(path)
+            .context(format!("Failed to read config file '{}'", path.display()).red())?;
+
+        // The toml error already names the offending key and points to its line
+        toml::from_str(&contents)
+            .map_err(|e| anyhow!(format!("Invalid config file '{}': {}", path.display(), e).red()))
+    }
+}
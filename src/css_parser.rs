@@ -0,0 +1,462 @@
+// A small css tokenizer and rule parser, following the tokenization of the css syntax spec closely enough to read
+// any stylesheet the same way whatever its formatting: minified on a single line, with comments, with strings
+// that contain braces or with escaped identifiers. Only what img-dumper needs from a stylesheet is kept.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    // unescaped, like `fa-bars` for `fa-bars` or `1x` for `\31 x`
+    Ident(String),
+    // the name of a function, without the `(`
+    Function(String),
+    // without the `@`
+    AtKeyword(String),
+    // without the `#`
+    Hash(String),
+    // unescaped, without the quotes
+    String(String),
+    // the content of an unquoted `url(...)`, a quoted one is a `Function` followed by a `String`
+    Url(String),
+    // with its unit or `%`, as it was written
+    Number(String),
+    Delim(char),
+    Colon,
+    Semicolon,
+    Comma,
+    OpenBrace,
+    CloseBrace,
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+    Whitespace,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    // lowercase, except for custom properties
+    pub name: String,
+    // without the surrounding whitespace and without `!important`
+    pub value: Vec<Token>,
+}
+
+// A `selectors { declarations }` rule, wherever it was in the stylesheet, including inside `@media` and `@supports`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleRule {
+    // one per selector of the comma separated list, without the surrounding whitespace
+    pub selectors: Vec<Vec<Token>>,
+    pub declarations: Vec<Declaration>,
+}
+
+// the at-rules whose block contains rules, the blocks of the others (like `@font-face`) contain declarations
+const GROUPING_AT_RULES: &[&str] = &["media", "supports", "layer", "container", "document", "-moz-document", "scope"];
+
+// Comments are dropped, every other character of the css ends up in a token
+pub fn tokenize(css: &str) -> Vec<Token> {
+    let chars: Vec<char> = css.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i = find_comment_end(&chars, i + 2);
+                continue;
+            }
+            c if c.is_whitespace() => {
+                while i < chars.len() && chars[i].is_whitespace() {
+                    i += 1;
+                }
+                tokens.push(Token::Whitespace);
+                continue;
+            }
+            '"' | '\'' => {
+                let (value, end) = consume_string(&chars, i + 1, c);
+                tokens.push(Token::String(value));
+                i = end;
+                continue;
+            }
+            '#' if chars.get(i + 1).is_some_and(|&next| is_name_char(next)) || starts_escape(&chars, i + 1) => {
+                let (name, end) = consume_name(&chars, i + 1);
+                tokens.push(Token::Hash(name));
+                i = end;
+                continue;
+            }
+            '@' if starts_ident(&chars, i + 1) => {
+                let (name, end) = consume_name(&chars, i + 1);
+                tokens.push(Token::AtKeyword(name));
+                i = end;
+                continue;
+            }
+            c if c.is_ascii_digit() || (matches!(c, '.' | '+' | '-') && starts_number(&chars, i)) => {
+                let end = consume_number(&chars, i);
+                tokens.push(Token::Number(chars[i..end].iter().collect()));
+                i = end;
+                continue;
+            }
+            _ if starts_ident(&chars, i) => {
+                let (name, end) = consume_name(&chars, i);
+                if chars.get(end) == Some(&'(') {
+                    if name.eq_ignore_ascii_case("url") {
+                        let value_start = skip_whitespace(&chars, end + 1);
+                        if !matches!(chars.get(value_start), Some('"' | '\'')) {
+                            let (url, url_end) = consume_unquoted_url(&chars, value_start);
+                            tokens.push(Token::Url(url));
+                            i = url_end;
+                            continue;
+                        }
+                    }
+                    tokens.push(Token::Function(name));
+                    i = end + 1;
+                } else {
+                    tokens.push(Token::Ident(name));
+                    i = end;
+                }
+                continue;
+            }
+            ':' => tokens.push(Token::Colon),
+            ';' => tokens.push(Token::Semicolon),
+            ',' => tokens.push(Token::Comma),
+            '{' => tokens.push(Token::OpenBrace),
+            '}' => tokens.push(Token::CloseBrace),
+            '(' => tokens.push(Token::OpenParen),
+            ')' => tokens.push(Token::CloseParen),
+            '[' => tokens.push(Token::OpenBracket),
+            ']' => tokens.push(Token::CloseBracket),
+            c => tokens.push(Token::Delim(c)),
+        }
+        i += 1;
+    }
+
+    tokens
+}
+
+// Every style rule of the stylesheet, in order. Unbalanced blocks end with the stylesheet, like browsers do.
+pub fn parse_style_rules(css: &str) -> Vec<StyleRule> {
+    let tokens = tokenize(css);
+    let mut rules = Vec::new();
+    parse_rules(&tokens, &mut rules);
+    rules
+}
+
+// Replaces every `url(...)` of the css that `get_new_url` returns Some for with `url("<new url>")`. The url is given
+// unescaped and without its quotes, whether it was written `url(a.woff)`, `url('a.woff')` or `url( "a.woff" )`.
+// Everything else is kept as it was written, including the comments and the strings that happen to contain `url(`.
+pub fn rewrite_urls(css: &str, mut get_new_url: impl FnMut(&str) -> Option<String>) -> String {
+    let chars: Vec<char> = css.chars().collect();
+    let mut rewritten = String::with_capacity(css.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let end = match chars[i] {
+            '/' if chars.get(i + 1) == Some(&'*') => find_comment_end(&chars, i + 2),
+            c @ ('"' | '\'') => consume_string(&chars, i + 1, c).1,
+            _ if starts_ident(&chars, i) => {
+                let (name, name_end) = consume_name(&chars, i);
+                match find_url(&chars, &name, name_end) {
+                    Some((url, url_end)) => {
+                        match get_new_url(&url) {
+                            Some(new_url) => rewritten.push_str(&format!("url(\"{}\")", escape_string(&new_url))),
+                            None => rewritten.extend(&chars[i..url_end]),
+                        }
+                        i = url_end;
+                        continue;
+                    }
+                    None => name_end,
+                }
+            }
+            _ => i + 1,
+        };
+        rewritten.extend(&chars[i..end]);
+        i = end;
+    }
+
+    rewritten
+}
+
+// Whether the url points at a file relative to the stylesheet, like `../fonts/a.woff`, rather than at a root relative
+// path, an absolute windows path, a data uri, a remote file or an element of the document like `#clip`
+pub fn is_relative_url(url: &str) -> bool {
+    let has_scheme = url.split_once(':').is_some_and(|(scheme, _)| {
+        // a single letter is the drive of a windows path, like `C:/fonts`
+        scheme.len() > 1 && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+    let is_drive_path = url.as_bytes().get(1) == Some(&b':') && url.as_bytes()[0].is_ascii_alphabetic();
+    !url.is_empty() && !url.starts_with(['/', '\\', '#']) && !has_scheme && !is_drive_path
+}
+
+// The path of the url and its query and fragment, like `fa.eot` and `?v=4.7.0#iefix`
+pub fn split_url_suffix(url: &str) -> (&str, &str) {
+    url.split_at(url.find(['?', '#']).unwrap_or(url.len()))
+}
+
+fn parse_rules(tokens: &[Token], rules: &mut Vec<StyleRule>) {
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Whitespace | Token::CloseBrace | Token::Semicolon => i += 1,
+            Token::AtKeyword(name) => {
+                // `@import url(...);` and the like end at the semicolon, the others have a block
+                let Some(block_start) = tokens[i..].iter().position(|token| matches!(token, Token::OpenBrace | Token::Semicolon))
+                    .map(|position| i + position) else { return };
+                if tokens[block_start] == Token::Semicolon {
+                    i = block_start + 1;
+                    continue;
+                }
+                let block_end = find_block_end(tokens, block_start);
+                if GROUPING_AT_RULES.contains(&name.to_lowercase().as_str()) {
+                    parse_rules(&tokens[block_start + 1..block_end], rules);
+                }
+                i = block_end + 1;
+            }
+            _ => {
+                let Some(block_start) = tokens[i..].iter().position(|token| *token == Token::OpenBrace).map(|position| i + position) else { return };
+                let block_end = find_block_end(tokens, block_start);
+                rules.push(StyleRule {
+                    selectors: split_top_level(&tokens[i..block_start], &Token::Comma).into_iter().map(trim_whitespace).map(|selector| selector.to_vec())
+                        .filter(|selector| !selector.is_empty())
+                        .collect(),
+                    declarations: parse_declarations(&tokens[block_start + 1..block_end]),
+                });
+                i = block_end + 1;
+            }
+        }
+    }
+}
+
+fn parse_declarations(tokens: &[Token]) -> Vec<Declaration> {
+    split_top_level(tokens, &Token::Semicolon).into_iter()
+        .filter_map(|declaration| {
+            let declaration = trim_whitespace(declaration);
+            let Some(Token::Ident(name)) = declaration.first() else { return None };
+            let colon = declaration.iter().position(|token| *token == Token::Colon)?;
+            let mut value = trim_whitespace(&declaration[colon + 1..]);
+            if let [rest @ .., Token::Delim('!'), Token::Ident(important)] = value {
+                if important.eq_ignore_ascii_case("important") {
+                    value = trim_whitespace(rest);
+                }
+            }
+            let name = if name.starts_with("--") { name.clone() } else { name.to_lowercase() };
+            Some(Declaration { name, value: value.to_vec() })
+        })
+        .collect()
+}
+
+// the index of the token that closes the block opened at `open_index`, or the length when it is never closed
+fn find_block_end(tokens: &[Token], open_index: usize) -> usize {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open_index) {
+        match token {
+            Token::OpenBrace | Token::OpenParen | Token::OpenBracket | Token::Function(_) => depth += 1,
+            Token::CloseBrace | Token::CloseParen | Token::CloseBracket => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+// splits at the separators that are not inside parentheses or brackets, like the commas of `.a, :is(.b, .c)`
+fn split_top_level<'a>(tokens: &'a [Token], separator: &Token) -> Vec<&'a [Token]> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::OpenBrace | Token::OpenParen | Token::OpenBracket | Token::Function(_) => depth += 1,
+            Token::CloseBrace | Token::CloseParen | Token::CloseBracket => depth -= 1,
+            token if token == separator && depth == 0 => {
+                parts.push(&tokens[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&tokens[start..]);
+    parts
+}
+
+fn trim_whitespace(tokens: &[Token]) -> &[Token] {
+    let start = tokens.iter().position(|token| *token != Token::Whitespace).unwrap_or(tokens.len());
+    let end = tokens.iter().rposition(|token| *token != Token::Whitespace).map_or(start, |position| position + 1);
+    &tokens[start..end]
+}
+
+fn find_comment_end(chars: &[char], start: usize) -> usize {
+    (start..chars.len().saturating_sub(1))
+        .find(|&i| chars[i] == '*' && chars[i + 1] == '/')
+        .map_or(chars.len(), |i| i + 2)
+}
+
+fn skip_whitespace(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() && chars[i].is_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+fn is_name_start_char(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start_char(c) || c.is_ascii_digit() || c == '-'
+}
+
+// a backslash that is not followed by a newline
+fn starts_escape(chars: &[char], i: usize) -> bool {
+    chars.get(i) == Some(&'\\') && chars.get(i + 1).is_some_and(|&next| next != '\n')
+}
+
+fn starts_ident(chars: &[char], i: usize) -> bool {
+    match chars.get(i) {
+        Some('-') => chars.get(i + 1).is_some_and(|&next| is_name_start_char(next) || next == '-') || starts_escape(chars, i + 1),
+        Some('\\') => starts_escape(chars, i),
+        Some(&c) => is_name_start_char(c),
+        None => false,
+    }
+}
+
+fn starts_number(chars: &[char], i: usize) -> bool {
+    let digit_at = |index: usize| chars.get(index).is_some_and(|c| c.is_ascii_digit());
+    match chars[i] {
+        '+' | '-' => digit_at(i + 1) || (chars.get(i + 1) == Some(&'.') && digit_at(i + 2)),
+        '.' => digit_at(i + 1),
+        c => c.is_ascii_digit(),
+    }
+}
+
+// the number with its unit, like `-1.5em`, `10%` or `1e3`
+fn consume_number(chars: &[char], start: usize) -> usize {
+    let mut i = start;
+    if matches!(chars[i], '+' | '-') {
+        i += 1;
+    }
+    while i < chars.len() && (chars[i].is_ascii_digit() || (chars[i] == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()))) {
+        i += 1;
+    }
+    if chars.get(i) == Some(&'%') {
+        return i + 1;
+    }
+    if starts_ident(chars, i) {
+        return consume_name(chars, i).1;
+    }
+    i
+}
+
+// the unescaped name and the index after it
+fn consume_name(chars: &[char], start: usize) -> (String, usize) {
+    let mut name = String::new();
+    let mut i = start;
+    while i < chars.len() {
+        if starts_escape(chars, i) {
+            let (c, end) = consume_escape(chars, i + 1);
+            name.push(c);
+            i = end;
+        } else if is_name_char(chars[i]) {
+            name.push(chars[i]);
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    (name, i)
+}
+
+// `\f0c9 ` is U+F0C9 (the whitespace after the hex digits is part of the escape), `\:` is `:`
+fn consume_escape(chars: &[char], start: usize) -> (char, usize) {
+    let hex_length = chars[start..].iter().take(6).take_while(|c| c.is_ascii_hexdigit()).count();
+    if hex_length == 0 {
+        return chars.get(start).map_or(('\u{FFFD}', start), |&c| (c, start + 1));
+    }
+    let hex: String = chars[start..start + hex_length].iter().collect();
+    let c = u32::from_str_radix(&hex, 16).ok()
+        .filter(|&codepoint| codepoint != 0)
+        .and_then(char::from_u32)
+        .unwrap_or('\u{FFFD}');
+    let mut end = start + hex_length;
+    if chars.get(end).is_some_and(|c| c.is_whitespace()) {
+        end += 1;
+    }
+    (c, end)
+}
+
+// the unescaped content and the index after the closing quote. An unclosed string ends at the newline, like in browsers.
+fn consume_string(chars: &[char], start: usize, quote: char) -> (String, usize) {
+    let mut value = String::new();
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            c if c == quote => return (value, i + 1),
+            '\n' => return (value, i),
+            '\\' => match chars.get(i + 1) {
+                // an escaped newline continues the string on the next line
+                Some('\n') => i += 2,
+                Some(_) => {
+                    let (c, end) = consume_escape(chars, i + 1);
+                    value.push(c);
+                    i = end;
+                }
+                None => i += 1,
+            },
+            c => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+    (value, i)
+}
+
+// The unescaped url and the index after the `)`, when the name that ends at `name_end` starts a `url(...)`.
+// A quoted url that is followed by anything else than the `)` is not one, like in browsers.
+fn find_url(chars: &[char], name: &str, name_end: usize) -> Option<(String, usize)> {
+    if !name.eq_ignore_ascii_case("url") || chars.get(name_end) != Some(&'(') {
+        return None;
+    }
+    let value_start = skip_whitespace(chars, name_end + 1);
+    match chars.get(value_start) {
+        Some(&quote @ ('"' | '\'')) => {
+            let (url, string_end) = consume_string(chars, value_start + 1, quote);
+            let close_index = skip_whitespace(chars, string_end);
+            (chars.get(close_index) == Some(&')')).then_some((url, close_index + 1))
+        }
+        _ => Some(consume_unquoted_url(chars, value_start)),
+    }
+}
+
+// the content of a double quoted css string
+fn escape_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\a "),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// the trimmed content of `url(...)` and the index after the `)`
+fn consume_unquoted_url(chars: &[char], start: usize) -> (String, usize) {
+    let mut url = String::new();
+    let mut i = start;
+    while i < chars.len() && chars[i] != ')' {
+        if starts_escape(chars, i) {
+            let (c, end) = consume_escape(chars, i + 1);
+            url.push(c);
+            i = end;
+        } else {
+            url.push(chars[i]);
+            i += 1;
+        }
+    }
+    (url.trim_end().to_owned(), (i + 1).min(chars.len()))
+}
@@ -0,0 +1,45 @@
+use crate::{renderer::Renderer, ScanResult};
+
+// Renders one row per image, with a header row, following RFC 4180 so that spreadsheets can open it directly.
+pub struct CsvRenderer;
+
+impl Renderer for CsvRenderer {
+    fn get_extension(&self) -> &'static str {
+        "csv"
+    }
+
+    fn render(&self, scan_result: &ScanResult) -> anyhow::Result<String> {
+        Ok(generate_csv_as_string(scan_result))
+    }
+}
+
+pub fn generate_csv_as_string(scan_result: &ScanResult) -> String {
+    let mut csv = String::with_capacity(1000);
+    push_row(&mut csv, &["project", "name", "path", "extension"]);
+    for project_dir in scan_result.projects.values() {
+        for image in &project_dir.images {
+            push_row(&mut csv, &[&project_dir.name, &image.name, &image.path, &image.extension]);
+        }
+    }
+
+    csv
+}
+
+fn push_row(csv: &mut String, fields: &[&str]) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            csv.push(',');
+        }
+        csv.push_str(&escape_field(field));
+    }
+    csv.push_str("\r\n");
+}
+
+// fields that contain a separator, a quote or a new line are quoted, doubling their quotes
+fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{html_escape::{encode_url_path, escape_html_attr as attr, escape_html_text as text}, scan_diff::{ImageChange, ScanDiff, SnapshotImage},
+    utils::format_bytes, VERSION_ID};
+
+// Renders the changes between two scans, with the image before and after every change.
+// The images are shown from the thumbnails that the json exports keep, or from the files where the scans found them
+// when there is none, like for a scan of the current tree. The previous content of a modified or removed image is only
+// shown from a thumbnail, since its file is gone, so it is marked as not kept in the exports of older versions.
+pub fn generate_diff_html_page_as_string(diff: &ScanDiff) -> String {
+    let mut html = String::from("<html lang='en'> <head> <meta charset='utf-8'> <title>Icons diff</title> </head> <body> <div id='page-container'>");
+
+    html += &format!(
+    "<div class='diff-header'>
+        <h1>Image changes</h1>
+        <span>from <b>{}</b> ({})</span>
+        <span>to <b>{}</b> ({})</span>
+        <span class='diff-summary'>{} added, {} removed, {} renamed, {} modified</span>
+        <span class='date-marker'>{}</span>
+    </div>", text(&diff.old.label), text(&diff.old.generated_at), text(&diff.new.label), text(&diff.new.generated_at),
+        diff.count_changes("added"), diff.count_changes("removed"), diff.count_changes("renamed"), diff.count_changes("modified"), VERSION_ID);
+
+    if diff.is_empty() {
+        html += "<p class='no-changes'>Nothing changed</p>";
+    }
+
+    for project_diff in &diff.projects {
+        html += &format!("<div class='project-area'><h2>{}</h2><table class='changes'>", text(&project_diff.name));
+        for change in &project_diff.changes {
+            let (before, after) = match change {
+                ImageChange::Added(img) => (get_thumbnail(None), get_thumbnail(Some(img))),
+                ImageChange::Removed(img) => (get_kept_thumbnail(img), get_thumbnail(None)),
+                // the content is the same, and the old path is gone
+                ImageChange::Renamed { new, .. } => (get_thumbnail(Some(new)), get_thumbnail(Some(new))),
+                // the file at the path has the new content now
+                ImageChange::Modified { old, new } => (get_kept_thumbnail(old), get_thumbnail(Some(new))),
+            };
+            html += &format!("<tr class='change {}'> <td><span class='change-kind'>{}</span></td> <td>{}</td> <td>{}</td> <td class='change-paths'>{}</td> </tr>",
+                change.get_name(), change.get_name(), before, after, get_paths_description(change));
+        }
+        html += "</table></div>";
+    }
+
+    if !diff.icon_sets.is_empty() {
+        html += "<div class='project-area'><h2>Icon classes</h2>";
+        for icon_set_diff in &diff.icon_sets {
+            html += &format!("<h3>{}</h3><ul class='class-names'>", text(&icon_set_diff.id));
+            for class_name in &icon_set_diff.added_class_names {
+                html += &format!("<li class='added'>+ {}</li>", text(class_name));
+            }
+            for class_name in &icon_set_diff.removed_class_names {
+                html += &format!("<li class='removed'>- {}</li>", text(class_name));
+            }
+            html += "</ul>";
+        }
+        html += "</div>";
+    }
+
+    html += "</div></body>";
+    html += get_css_string();
+    html += "</html>";
+
+    html
+}
+
+const UNAVAILABLE_THUMBNAIL: &str = "<div class='thumbnail unavailable' title='The snapshot has no thumbnail of the previous content'>\
+    <span>not kept</span></div>";
+
+fn get_thumbnail(img: Option<&SnapshotImage>) -> String {
+    let Some(img) = img else { return "<div class='thumbnail empty'></div>".to_owned() };
+    let src = match &img.thumbnail {
+        Some(thumbnail) => thumbnail.clone(),
+        None => encode_url_path(&img.path),
+    };
+    format!("<div class='thumbnail'><img src=\"{}\" alt=\"\" loading='lazy' /></div>", attr(&src))
+}
+
+// the thumbnail of an image whose file doesn't have that content anymore, only if the snapshot kept one
+fn get_kept_thumbnail(img: &SnapshotImage) -> String {
+    match img.thumbnail {
+        Some(_) => get_thumbnail(Some(img)),
+        None => UNAVAILABLE_THUMBNAIL.to_owned(),
+    }
+}
+
+fn get_paths_description(change: &ImageChange) -> String {
+    match change {
+        ImageChange::Added(img) | ImageChange::Removed(img) =>
+            format!("<span>{}</span><span class='size'>{}</span>", text(&img.relative_path), format_bytes(img.size_bytes)),
+        ImageChange::Renamed { old, new } =>
+            format!("<span>{}</span><span>&rarr; {}</span><span class='size'>{}</span>", text(&old.relative_path), text(&new.relative_path),
+                format_bytes(new.size_bytes)),
+        ImageChange::Modified { old, new } =>
+            format!("<span>{}</span><span class='size'>{} &rarr; {}</span>", text(&new.relative_path), format_bytes(old.size_bytes),
+                format_bytes(new.size_bytes)),
+    }
+}
+
+fn get_css_string() -> &'static str {
+    "<style>
+        body {
+            background-color: #f1f1f1;
+            font-family: Arial, Helvetica, sans-serif;
+        }
+
+        .diff-header {
+            display: flex;
+            flex-direction: column;
+            row-gap: 0.3em;
+            padding: 0.5em 1em 1em;
+            box-shadow: 0 2px 4px rgba(0, 0, 0, 0.4);
+            background-color: #d3d3d3;
+        }
+
+        .diff-header h1 {
+            margin: 0 0 0.2em;
+        }
+
+        .diff-summary {
+            font-weight: bold;
+        }
+
+        .date-marker {
+            color: #3a3a3a;
+            font-size: 0.85em;
+            font-style: italic;
+        }
+
+        .no-changes {
+            margin: 2em;
+            font-size: 1.2em;
+        }
+
+        .project-area {
+            margin: 1.5em 1em;
+        }
+
+        .changes {
+            border-collapse: collapse;
+        }
+
+        .changes td {
+            padding: 0.3em 0.6em;
+            border-bottom: 1px solid #d3d3d3;
+            vertical-align: middle;
+        }
+
+        .change-kind {
+            display: inline-block;
+            min-width: 5.5em;
+            padding: 0.15em 0.4em;
+            border-radius: 3px;
+            color: white;
+            font-size: 0.85em;
+            text-align: center;
+        }
+
+        .added .change-kind { background-color: #2e8b57; }
+        .removed .change-kind { background-color: #c0392b; }
+        .renamed .change-kind { background-color: #2c6fbb; }
+        .modified .change-kind { background-color: #d68910; }
+
+        .thumbnail {
+            display: flex;
+            align-items: center;
+            justify-content: center;
+            width: 64px;
+            height: 64px;
+            background-color: white;
+            border: 1px solid #d3d3d3;
+        }
+
+        .thumbnail.empty {
+            background-color: transparent;
+            border-style: dashed;
+        }
+
+        .thumbnail.unavailable {
+            border-style: dashed;
+            color: #757575;
+            font-size: 0.75em;
+            font-style: italic;
+            text-align: center;
+        }
+
+        .thumbnail img {
+            max-width: 60px;
+            max-height: 60px;
+        }
+
+        .change-paths span {
+            display: block;
+        }
+
+        .change-paths .size {
+            color: #3a3a3a;
+            font-size: 0.85em;
+        }
+
+        .class-names {
+            list-style: none;
+            font-family: monospace;
+        }
+
+        .class-names .added { color: #2e8b57; }
+        .class-names .removed { color: #c0392b; }
+    </style>"
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::scan_diff::{diff_snapshots, Snapshot};
+
+    use super::*;
+
+    fn snapshot(path: &str, content_hash: &str, thumbnail: Option<&str>) -> Snapshot {
+        let img = SnapshotImage {
+            project_name: "p".to_owned(),
+            path: format!("/root/{}", path),
+            relative_path: path.to_owned(),
+            size_bytes: 1,
+            content_hash: Some(content_hash.to_owned()),
+            thumbnail: thumbnail.map(str::to_owned),
+        };
+        Snapshot { label: String::new(), generated_at: String::new(), root_dir: "/root".to_owned(), images: BTreeMap::from([(path.to_owned(), img)]),
+            icon_sets: BTreeMap::new() }
+    }
+
+    #[test]
+    fn the_previous_content_is_only_shown_from_a_kept_thumbnail() {
+        let html = generate_diff_html_page_as_string(&diff_snapshots(snapshot("p/a.png", "1", None), snapshot("p/a.png", "2", None)));
+        assert_eq!(html.matches("<img src=\"/root/p/a.png\"").count(), 1);
+        assert!(html.contains(UNAVAILABLE_THUMBNAIL));
+
+        let html = generate_diff_html_page_as_string(&diff_snapshots(snapshot("p/a.png", "1", Some("data:image/png;base64,b2xk")),
+            snapshot("p/a.png", "2", None)));
+        assert!(html.contains("<img src=\"data:image/png;base64,b2xk\""));
+        assert_eq!(html.matches("<img src=\"/root/p/a.png\"").count(), 1);
+        assert!(!html.contains(UNAVAILABLE_THUMBNAIL));
+
+        // a removed file is gone, only its thumbnail can be shown
+        let html = generate_diff_html_page_as_string(&diff_snapshots(snapshot("p/a.png", "1", None),
+            Snapshot { images: BTreeMap::new(), ..snapshot("p/a.png", "1", None) }));
+        assert!(html.contains(UNAVAILABLE_THUMBNAIL) && !html.contains("/root/p/a.png\""));
+
+        // a renamed image has the same content on both sides, at the path that still exists
+        let html = generate_diff_html_page_as_string(&diff_snapshots(snapshot("p/a.png", "1", None), snapshot("p/b.png", "1", None)));
+        assert_eq!(html.matches("<img src=\"/root/p/b.png\"").count(), 2);
+        assert!(!html.contains("/root/p/a.png\""));
+    }
+}
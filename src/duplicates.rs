@@ -0,0 +1,80 @@
+use std::{collections::{BTreeMap, HashMap}, fs::File, io, path::Path};
+
+use rayon::prelude::*;
+
+use crate::ProjectDir;
+
+// Where one of the identical files lives
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ImageLocation {
+    pub project_name: String,
+    pub path: String,
+}
+
+// Files with exactly the same content, possibly in different projects and with different names
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    // hex encoded blake3 hash of the content
+    pub content_hash: String,
+    // size of each one of the files
+    pub size_bytes: u64,
+    // sorted by project name and path, there are always at least two
+    pub locations: Vec<ImageLocation>,
+}
+
+impl DuplicateGroup {
+    // the bytes that would be freed if only one copy was kept
+    pub fn get_wasted_bytes(&self) -> u64 {
+        self.size_bytes * (self.locations.len() as u64 - 1)
+    }
+}
+
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+// Hashes the content of every image that has no hash yet, in parallel. Images that can't be read are left without a hash.
+pub fn hash_images(projects: &mut BTreeMap<String, ProjectDir>) {
+    let mut images: Vec<_> = projects.values_mut()
+        .flat_map(|project_dir| project_dir.images.iter_mut())
+        .filter(|img| img.content_hash.is_none())
+        .collect();
+    images.par_iter_mut().for_each(|img| {
+        img.content_hash = hash_file(Path::new(&img.path)).ok();
+    });
+}
+
+// Groups the images that have identical content across all projects. The groups that waste the most bytes come first.
+pub fn find_duplicates(projects: &BTreeMap<String, ProjectDir>) -> Vec<DuplicateGroup> {
+    let mut groups: HashMap<&str, DuplicateGroup> = HashMap::new();
+    for project_dir in projects.values() {
+        for img in &project_dir.images {
+            let Some(content_hash) = &img.content_hash else { continue };
+            // empty files are all "identical", but they waste nothing
+            if img.size_bytes == 0 {
+                continue;
+            }
+            groups
+                .entry(content_hash)
+                .or_insert_with(|| DuplicateGroup {
+                    content_hash: content_hash.clone(),
+                    size_bytes: img.size_bytes,
+                    locations: Vec::new(),
+                })
+                .locations
+                .push(ImageLocation { project_name: project_dir.name.clone(), path: img.path.clone() });
+        }
+    }
+
+    let mut duplicates: Vec<DuplicateGroup> = groups.into_values().filter(|group| group.locations.len() > 1).collect();
+    duplicates.iter_mut().for_each(|group| group.locations.sort());
+    duplicates.sort_by(|a, b| b.get_wasted_bytes().cmp(&a.get_wasted_bytes()).then_with(|| a.locations.cmp(&b.locations)));
+
+    duplicates
+}
+
+pub fn get_total_wasted_bytes(duplicates: &[DuplicateGroup]) -> u64 {
+    duplicates.iter().map(|group| group.get_wasted_bytes()).sum()
+}
@@ -0,0 +1,113 @@
+use std::{fs::{self, OpenOptions}, io::{self, Write}};
+
+use anyhow::Context;
+use chrono::{DateTime, Local};
+use colored::*;
+
+use crate::{json_export::generate_json_as_string, scan_diff::{get_relative_path, Snapshot}, utils::join_paths, ScanResult};
+
+const SNAPSHOT_FILE_PREFIX: &str = "scan-";
+
+// The path of the most recent snapshot in the history directory, None when there is none yet.
+// The file names contain the time of the scan, so the most recent one has the last time in alphabetical order,
+// and the highest suffix among the snapshots of that time.
+pub fn find_latest_snapshot(history_dir: &str) -> anyhow::Result<Option<String>> {
+    let entries = fs::read_dir(history_dir).context(format!("Unable to read the history directory `{}`", history_dir).red())?;
+    let latest_file_name = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str().map(|name| name.to_owned()))
+        .filter(|name| get_snapshot_order(name).is_some())
+        .max_by(|a, b| get_snapshot_order(a).cmp(&get_snapshot_order(b)));
+
+    Ok(latest_file_name.map(|name| join_paths(history_dir, &name, "/")))
+}
+
+// The time part and the suffix of the file name of a snapshot, which is 1 when there is none. None for the other files.
+// The suffix is compared as a number, so that `_10` comes after `_9`.
+fn get_snapshot_order(file_name: &str) -> Option<(&str, u32)> {
+    let stem = file_name.strip_prefix(SNAPSHOT_FILE_PREFIX)?.strip_suffix(".json")?;
+    match stem.split_once('_') {
+        Some((time, suffix)) => Some((time, suffix.parse().ok()?)),
+        None => Some((stem, 1)),
+    }
+}
+
+// Saves the json export of the scan in the history directory, and returns its path. The name has the time of the scan
+// to the millisecond, like `scan-20240131-142501-123.json`. A snapshot never replaces another one: when the name is taken,
+// by another run in the same millisecond, `_2`, `_3`... is appended, which `find_latest_snapshot` compares as a number.
+pub fn save_snapshot(history_dir: &str, scan_result: &ScanResult) -> anyhow::Result<String> {
+    let json = generate_json_as_string(scan_result)?;
+    let stem = format!("{}{}", SNAPSHOT_FILE_PREFIX, scan_result.scanned_at.format("%Y%m%d-%H%M%S-%3f"));
+    let mut suffix = 1;
+    loop {
+        let file_name = match suffix {
+            1 => format!("{}.json", stem),
+            _ => format!("{}_{}.json", stem, suffix),
+        };
+        let path = join_paths(history_dir, &file_name, "/");
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(json.as_bytes()).context(format!("Failed to save the snapshot `{}`", path).red())?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => suffix += 1,
+            Err(e) => return Err(e).context(format!("Failed to save the snapshot `{}`", path).red()),
+        }
+    }
+}
+
+// Marks the images whose path (relative to the root directory) is not in the previous snapshot as new.
+pub fn mark_new_images(scan_result: &mut ScanResult, previous: &Snapshot) {
+    // the snapshots are written by `save_snapshot`, so their time is always valid
+    let Ok(previous_scanned_at) = DateTime::parse_from_rfc3339(&previous.generated_at) else { return };
+    scan_result.new_since = Some(previous_scanned_at.with_timezone(&Local));
+
+    let root_dir = scan_result.root_dir.clone();
+    for img in scan_result.projects.values_mut().flat_map(|project_dir| project_dir.images.iter_mut()) {
+        img.is_new = !previous.images.contains_key(&get_relative_path(&img.path, &root_dir));
+    }
+}
+
+pub fn count_new_images(scan_result: &ScanResult) -> usize {
+    scan_result.projects.values().flat_map(|project_dir| &project_dir.images).filter(|img| img.is_new).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn snapshots_of_the_same_millisecond_do_not_replace_each_other() {
+        let history_dir = std::env::temp_dir().join(format!("img-dumper-history-{}", std::process::id())).to_string_lossy().replace('\\', "/");
+        let _ = fs::remove_dir_all(&history_dir);
+        fs::create_dir_all(&history_dir).unwrap();
+        let scan_result = ScanResult {
+            scanned_at: Local::now(),
+            root_dir: "/root".to_owned(),
+            extensions: Vec::new(),
+            projects: BTreeMap::new(),
+            duplicates: Vec::new(),
+            similar_clusters: Vec::new(),
+            new_since: None,
+            icon_sets: Vec::new(),
+        };
+
+        let paths: Vec<String> = (0..11).map(|_| save_snapshot(&history_dir, &scan_result).unwrap()).collect();
+        let latest = find_latest_snapshot(&history_dir).unwrap();
+        fs::remove_dir_all(&history_dir).unwrap();
+
+        assert!(paths[1].ends_with("_2.json") && paths[10].ends_with("_11.json"), "{:?}", paths);
+        // `_11` is the latest, even though `_9` comes after it in alphabetical order
+        assert_eq!(latest.as_ref(), Some(&paths[10]));
+    }
+
+    #[test]
+    fn snapshot_suffixes_are_compared_as_numbers() {
+        assert_eq!(get_snapshot_order("scan-20240131-142501-123.json"), Some(("20240131-142501-123", 1)));
+        assert_eq!(get_snapshot_order("scan-20240131-142501-123_10.json"), Some(("20240131-142501-123", 10)));
+        assert_eq!(get_snapshot_order("scan-20240131-142501-123_copy.json"), None);
+        assert_eq!(get_snapshot_order("notes.json"), None);
+    }
+}
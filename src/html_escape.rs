@@ -0,0 +1,79 @@
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+// Everything except the unreserved characters and the path separators is percent-encoded,
+// so that spaces, `#`, `?`, `%` and non-ASCII characters of a file path survive as a url.
+const URL_PATH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'/')
+    .remove(b':');
+
+// for text between tags
+pub fn escape_html_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// for attribute values, safe inside both single and double quotes
+pub fn escape_html_attr(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Turns a local file path into a percent-encoded url path. The result still needs `escape_html_attr`
+// to be placed inside an attribute, although after the encoding there is nothing left for it to escape.
+pub fn encode_url_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let encoded = utf8_percent_encode(&path, URL_PATH_ENCODE_SET).to_string();
+    // `C:/dir` would be read as a url with the `c:` scheme, `/C:/dir` works both for file:// pages and for the local server
+    if path.as_bytes().get(1) == Some(&b':') && path.as_bytes()[0].is_ascii_alphabetic() {
+        return format!("/{}", encoded);
+    }
+    encoded
+}
+
+// The local file path of a url path made by `encode_url_path`
+pub fn decode_url_path(url_path: &str) -> String {
+    let decoded = percent_decode_str(url_path).decode_utf8_lossy();
+    match decoded.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => decoded[1..].to_owned(),
+        _ => decoded.into_owned(),
+    }
+}
+
+// for a javascript string literal inside a <script> element, including the surrounding quotes
+pub fn escape_js_string(value: &str) -> String {
+    escape_js_value(&value)
+}
+
+// any serializable value as a javascript literal, for code inside a <script> element
+pub fn escape_js_value(value: &impl serde::Serialize) -> String {
+    let literal = serde_json::to_string(value).unwrap_or_else(|_| "null".to_owned());
+    // `</script>` would end the element regardless of the quotes, and `<!--` changes how it is parsed
+    literal.replace("</", "<\\/").replace("<!--", "<\\!--")
+}
+
+// for css text inside a <style> element, where only a closing tag could break out
+pub fn escape_style_content(css: &str) -> String {
+    css.replace("</", "<\\/")
+}
@@ -0,0 +1,1064 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{html_escape::{encode_url_path, escape_html_attr as attr, escape_html_text as text, escape_js_string, escape_js_value, escape_style_content},
+    duplicates::{get_total_wasted_bytes, DuplicateGroup}, history::count_new_images, portable::{make_font_data_uris, make_image_data_uris, rewrite_css_urls}, references::BrokenReferenceKind, renderer::Renderer, similarity::SimilarCluster, special_files::IconSet, utils::format_bytes, Img, ScanResult, VERSION_ID};
+
+// Renders the interactive page, that is searchable and contains extension filters.
+#[derive(Debug, Default)]
+pub struct HtmlRenderer {
+    is_portable: bool,
+}
+
+impl HtmlRenderer {
+    // embeds the images and the fonts of the icon sets, see `generate_portable_html_page_as_string`
+    pub fn portable() -> Self {
+        Self { is_portable: true }
+    }
+}
+
+impl Renderer for HtmlRenderer {
+    fn get_extension(&self) -> &'static str {
+        "html"
+    }
+
+    fn render(&self, scan_result: &ScanResult) -> anyhow::Result<String> {
+        if self.is_portable {
+            generate_portable_html_page_as_string(scan_result)
+        } else {
+            generate_html_page_as_string(scan_result)
+        }
+    }
+}
+
+// Where the page links the files it shows from: the images and the fonts of the icon sets. A file is linked at its path,
+// unless it has a replacement, like its content when it is embedded or its copy in a bundle.
+struct AssetUrls {
+    // path -> url, empty when every file is linked at its path
+    urls: HashMap<String, String>,
+}
+
+impl AssetUrls {
+    fn get_src(&self, path: &str) -> String {
+        match self.urls.get(path) {
+            Some(url) => url.clone(),
+            None => encode_url_path(path),
+        }
+    }
+}
+
+// image path -> [[source path, line, snippet], ...], or null when the references were not searched for
+type ReferenceIndex<'a> = BTreeMap<&'a str, Option<Vec<(&'a str, usize, &'a str)>>>;
+
+fn get_javascript_string(scan_result: &ScanResult) -> String {
+    let mut js = 
+    "<script>
+    const inputElement = document.getElementById('search-input');
+    inputElement.addEventListener('input', handleSearchChange);
+
+    function handleSearchChange() {
+        let input, filter, uls, lis, a, i, span, txtValue;
+        input = document.getElementById('search-input');
+        filter = input.value.toUpperCase();
+        let relevant_lis_count = 0;
+        uls = document.querySelectorAll('ul.images-area');
+        for (ul of uls) {
+            parent_project_area_div = ul.parentElement;
+            lis = ul.getElementsByTagName('li');
+            for (i = 0; i < lis.length; i++) {
+                extensionSpan = lis[i].getElementsByTagName('span')[0];
+                extensionValue = extensionSpan.textContent || extensionSpan.innerText;
+                nameSpan = lis[i].getElementsByTagName('span')[1];
+                // the glyphs are also found by their aliases and their codepoint
+                nameValue = lis[i].getAttribute('data-search') || nameSpan.textContent || nameSpan.innerText;
+                if (nameValue.toUpperCase().indexOf(filter) > -1 && currentlySelectedExtensions.includes(extensionValue.toLowerCase())
+                        && (!isOnlyNewShown || lis[i].classList.contains('new'))) {
+                    lis[i].style.display = '';
+                    relevant_lis_count += 1;
+                } else {
+                    lis[i].style.display = 'none';
+                }
+            }
+            if (relevant_lis_count == 0) {
+                parent_project_area_div.style.display = 'none';
+            } else {
+                parent_project_area_div.style.display = 'block';
+            }
+            relevant_lis_count = 0;
+        }
+    }
+
+    // shows only the images that appeared since the previous snapshot, together with the other filters
+    let isOnlyNewShown = false;
+    function handleOnlyNewChange(event) {
+        isOnlyNewShown = event.currentTarget.checked;
+        handleSearchChange();
+    }
+
+    // handler to copy the folder of the path of <li> elements, that also shows where an image is used,
+    // and the codepoint or the html entity of a glyph
+    document.addEventListener('click', handleLiClick);
+    function handleLiClick($event) {
+        let target = $event.target;
+        const copyButton = target.closest('[data-copy]');
+        if (copyButton) {
+            copyToClipboard(copyButton.getAttribute('data-copy'), 'Copied ' + copyButton.getAttribute('data-copy') + ' to clipboard!');
+            return;
+        }
+        if (target.parentElement.tagName.toLowerCase() === 'li') {
+            target = target.parentElement;
+        }
+        if (target.tagName.toLowerCase() === 'li') {
+            let titleValue = target.getAttribute('data-path');
+            if (!titleValue) {
+                return;
+            }
+            showDetailsPanel(target, titleValue);
+            titleValue = titleValue.substring(0, titleValue.lastIndexOf('/'));
+            copyToClipboard(titleValue, 'Copied path to clipboard!');
+        }
+    }
+
+    function copyToClipboard(text, message) {
+        navigator.clipboard.writeText(text)
+            .then(() => {
+                console.log('Text copied to clipboard: ' + text);
+                const copyNotification = document.getElementById('copy-notification');
+                copyNotification.querySelector('span').textContent = message;
+                copyNotification.classList.add('show');
+                setTimeout(() => {
+                    copyNotification.classList.remove('show');
+                }, 1000);
+            })
+            .catch((error) => {
+                console.error('Error copying text to clipboard:', error);
+            });
+    }
+
+    // lists where the image of the clicked tile is used, from the index that is embedded at the end of the script
+    function showDetailsPanel(li, path) {
+        if (!Object.prototype.hasOwnProperty.call(imageReferences, path)) {
+            return;
+        }
+        const references = imageReferences[path];
+        const image = li.querySelector('img');
+        document.getElementById('details-image').src = image ? image.src : '';
+        document.getElementById('details-name').textContent = path.substring(path.lastIndexOf('/') + 1);
+        document.getElementById('details-path').textContent = path;
+
+        const summary = document.getElementById('details-summary');
+        const list = document.getElementById('details-references');
+        list.replaceChildren();
+        if (references === null) {
+            summary.textContent = 'The source files were not searched for references.';
+        } else if (references.length === 0) {
+            summary.textContent = 'Unused, no source file of the project points at it.';
+        } else {
+            summary.textContent = 'Used in ' + references.length + (references.length === 1 ? ' place:' : ' places:');
+        }
+        for (const [sourcePath, line, snippet] of references || []) {
+            const item = document.createElement('li');
+            const location = document.createElement('span');
+            location.className = 'details-location';
+            location.textContent = sourcePath + ':' + line;
+            const code = document.createElement('code');
+            code.textContent = snippet;
+            item.append(location, code);
+            list.appendChild(item);
+        }
+
+        document.getElementById('details-panel').style.display = '';
+    }
+
+    function hideDetailsPanel() {
+        document.getElementById('details-panel').style.display = 'none';
+    }
+    document.getElementById('details-close').addEventListener('click', hideDetailsPanel);
+    document.addEventListener('keydown', ($event) => {
+        if ($event.key === 'Escape') {
+            hideDetailsPanel();
+        }
+    });
+
+    function toggleProjectArea($event) {
+        let element = $event.currentTarget;
+        let downChild = element.querySelector('span.down');
+        let upChild = element.querySelector('span.up');
+        let ul = element.parentElement.parentElement.querySelector('ul.images-area, .duplicates-list, .similar-list, .broken-references-list');
+        console.log('ul: ', ul);
+
+        // down arrow is showing in the beginning
+        if (getComputedStyle(downChild).display !== 'none') {
+            downChild.style.display = 'none';
+            ul.style.display = 'none';
+        } else {
+            downChild.style.display = '';
+            ul.style.display = '';
+        }
+
+        if (getComputedStyle(upChild).display !== 'none') {
+            upChild.style.display = 'none';
+        } else {
+            upChild.style.display = '';
+        }
+    } 
+
+    function handleCheckboxChange(event) {
+        const checkbox = event.currentTarget;
+        const checkboxValue = checkbox.value;
+        const isChecked = checkbox.checked;
+      
+        if (isChecked) {
+          currentlySelectedExtensions.push(checkboxValue);
+        } else {
+          currentlySelectedExtensions = currentlySelectedExtensions.filter(
+            (ext) => ext !== checkboxValue
+          );
+        }
+      
+        let relevant_lis_count = 0;
+        const uls = document.querySelectorAll('ul.images-area');
+
+        inputFilter = document.getElementById('search-input').value.toUpperCase();
+
+        for (const ul of uls) {
+          const parent_project_area_div = ul.parentElement;
+          const lis = ul.getElementsByTagName('li');
+      
+          for (const li of lis) {
+            extensionSpan = li.getElementsByTagName('span')[0];
+            extensionValue = extensionSpan.textContent || extensionSpan.innerText;
+            nameSpan = li.getElementsByTagName('span')[1];
+            nameValue = li.getAttribute('data-search') || nameSpan.textContent || nameSpan.innerText;
+
+            if (currentlySelectedExtensions.includes(extensionValue.toLowerCase()) && (!nameValue || nameValue.toUpperCase().indexOf(inputFilter) > -1)
+                && (!isOnlyNewShown || li.classList.contains('new'))) {
+              if (window.getComputedStyle(li).display === 'none') {
+                li.style.display = '';
+              }
+              relevant_lis_count += 1;
+            } else {
+                if (window.getComputedStyle(li).display !== 'none') {
+                    li.style.display = 'none';
+                }
+            }
+          }
+      
+          if (relevant_lis_count === 0) {
+            parent_project_area_div.style.display = 'none';
+          } else {
+            parent_project_area_div.style.display = 'block';
+          }
+      
+          relevant_lis_count = 0;
+        }
+      }
+    ".to_owned();
+
+    let joined_values = scan_result
+    .extensions
+    .iter()
+    .map(|value| escape_js_string(value))
+    .collect::<Vec<String>>()
+    .join(",");
+
+    js.push_str(&format!("
+    let currentlySelectedExtensions = [{}];
+    ",joined_values));
+
+    let image_references: ReferenceIndex = scan_result.projects.values()
+        .flat_map(|project_dir| project_dir.images.iter())
+        .map(|img| (img.path.as_str(), img.references.as_ref().map(|references| references.iter()
+            .map(|reference| (reference.source_path.as_str(), reference.line, reference.snippet.as_str()))
+            .collect())))
+        .collect();
+    js.push_str(&format!("
+    const imageReferences = {};
+    ", escape_js_value(&image_references)));
+
+    js.push_str("</script>");
+
+    js
+}
+
+// Generates the whole interactive page, with its css and javascript embedded.
+pub fn generate_html_page_as_string(scan_result: &ScanResult) -> anyhow::Result<String> {
+    generate_html_page(scan_result, &AssetUrls { urls: HashMap::new() })
+}
+
+// Generates the page with every image embedded as a data uri (big rasters as a thumbnail) and with the fonts of the icon sets
+// inlined in the css, so that it can be opened on any machine.
+pub fn generate_portable_html_page_as_string(scan_result: &ScanResult) -> anyhow::Result<String> {
+    let mut urls = make_image_data_uris(&scan_result.projects);
+    urls.extend(make_font_data_uris(&scan_result.icon_sets));
+    generate_html_page(scan_result, &AssetUrls { urls })
+}
+
+// Generates the page of a bundle, where the images and the fonts are copied next to it.
+// `asset_paths` maps the path of every copied file to the path of its copy, relative to the page.
+pub fn generate_bundled_html_page_as_string(scan_result: &ScanResult, asset_paths: &BTreeMap<String, String>) -> anyhow::Result<String> {
+    let urls = asset_paths.iter()
+        .map(|(path, relative_path)| (path.clone(), encode_url_path(relative_path)))
+        .collect();
+    generate_html_page(scan_result, &AssetUrls { urls })
+}
+
+fn generate_html_page(scan_result: &ScanResult, asset_urls: &AssetUrls) -> anyhow::Result<String> {
+    let mut html = String::from("<html lang='en'> <head> <title>Spectre icons</title> </head> <body> <div id='page-container'>");
+    
+    html += 
+    "<div class='search-container'>
+        <div>
+            <label for='search-input'>Search:</label>
+            <input id='search-input' name='search-input'>
+        </div>
+        <div class='flex-center flex-1'>";
+        for extension in &scan_result.extensions {
+            html += &format!(
+            "<div class='checkbox-item'>
+                <input type='checkbox' id='checkbox-{}' name='checkbox-{}' value='{}' onchange='handleCheckboxChange(event)' checked>
+                <label for='checkbox-{}'>{}</label>
+            </div>", attr(extension), attr(extension), attr(extension), attr(extension), text(extension));
+        }
+        if let Some(new_since) = scan_result.new_since {
+            html += &format!(
+            "<div class='checkbox-item only-new-item' title='Only the images that appeared since the previous snapshot of {}'>
+                <input type='checkbox' id='only-new-checkbox' name='only-new-checkbox' onchange='handleOnlyNewChange(event)'>
+                <label for='only-new-checkbox'>only new ({})</label>
+            </div>", new_since.format("%d/%m/%Y - %H:%M:%S"), count_new_images(scan_result));
+        }
+    html += &format!(
+    "   </div>
+            <div class='date-marker-area'>
+                <span>{}</span>
+                <span>Generated at:</span>
+                <span>{}</span>
+            </div>
+    </div>", VERSION_ID, scan_result.scanned_at.format("%d/%m/%Y - %H:%M:%S"));
+
+    html +=
+    "<div id='details-panel' class='details-panel' style='display: none'>
+        <div class='details-header'>
+            <span id='details-name'></span>
+            <button id='details-close' type='button' title='Close (Esc)'>&#10005;</button>
+        </div>
+        <img id='details-image' alt='' />
+        <span id='details-path'></span>
+        <span id='details-summary'></span>
+        <ul id='details-references'></ul>
+    </div>";
+
+    html +=
+    "<div id='copy-notification' class='fade'>
+        <span> Copied path to clipboard!</span>
+    </div>";
+
+    for icon_set in &scan_result.icon_sets {
+        if !icon_set.glyphs.is_empty() {
+            html += &generate_html_string_from_classes(icon_set, "svg");
+        }
+    }
+
+    // projects that are only kept for their broken references are listed in their own section
+    for curr_project_dir in scan_result.projects.values().filter(|project_dir| !project_dir.images.is_empty()) {
+        html += &format!("<div class='project-area'>
+                            <div class='flex-center'>
+                                <div class='name-arrow-container' onclick='toggleProjectArea(event)'>
+                                    <span class='down arrow-utf-8'>&#9660</span>
+                                    <span class='up arrow-utf-8' style='display: none'>&#9650</span>
+                                    <h1 class='title margin-right-05'>{}</h1>
+                                </div>
+                                <span>({})</span>
+                            </div>
+                            <ul class='images-area'>", text(&curr_project_dir.name), text(&curr_project_dir.path));
+
+        for (i, image) in curr_project_dir.images.iter().enumerate() {
+        let (unused_class, unused_stamp) = if image.is_unused() { (" unused", " <span class='unused-stamp'>unused</span>") } else { ("", "") };
+        let (new_class, new_stamp) = if image.is_new { (" new", " <span class='new-stamp'>new</span>") } else { ("", "") };
+        html += &format!("<li class='image-container{}{}' title='{}' data-path='{}'> <div class='extension-stamp color-{}'> <span>{}</span> </div> <img src=\"{}\" alt=\"{}\" /> <span>{}</span>{}{} </li>
+                    ", unused_class, new_class, attr(&get_image_tooltip(image)), attr(&image.path), attr(&image.extension), text(&image.extension), attr(&asset_urls.get_src(&image.path)), i, text(&image.name), new_stamp, unused_stamp);
+        }
+
+        html += "</ul></div>";
+    }
+
+    if !scan_result.duplicates.is_empty() {
+        html += &generate_html_string_from_duplicates(&scan_result.duplicates, asset_urls);
+    }
+
+    if !scan_result.similar_clusters.is_empty() {
+        html += &generate_html_string_from_similar_clusters(&scan_result.similar_clusters, asset_urls);
+    }
+
+    if scan_result.projects.values().any(|project_dir| !project_dir.broken_references.is_empty()) {
+        html += &generate_html_string_from_broken_references(scan_result);
+    }
+
+    html += "
+    <footer class='author-area'>
+        <span>Petros Papatheodorou 2023</span>
+    </footer>";
+    
+    html += "</div></body>";
+    html += &get_css_string(&scan_result.icon_sets, asset_urls);
+    html += &get_javascript_string(scan_result);
+    html += "</html>";
+
+    Ok(html)
+}
+
+// the path of the image, followed by a line with what was read from its header
+fn get_image_tooltip(image: &Img) -> String {
+    let mut facts = Vec::with_capacity(3);
+    if let Some(dimensions) = image.header.dimensions {
+        facts.push(format!("{} x {} px", dimensions.width, dimensions.height));
+    }
+    facts.push(format_bytes(image.size_bytes));
+    if let Some(modified) = image.modified {
+        facts.push(format!("modified {}", modified.format("%d/%m/%Y %H:%M")));
+    }
+
+    let mut tooltip = format!("{}\n{}", image.path, facts.join(" | "));
+    match image.header.format {
+        Some(format) if !format.matches_extension(&image.extension) => {
+            tooltip += &format!("\nthe content is actually {}", format.get_name());
+        }
+        None => tooltip += "\nthe content is not a recognised image format",
+        _ => {}
+    }
+    if image.is_new {
+        tooltip += "\nnew since the previous snapshot";
+    }
+    match &image.references {
+        Some(references) if references.is_empty() => tooltip += "\nunused, no source file of the project points at it",
+        Some(references) => tooltip += &format!("\nreferenced {} times", references.len()),
+        None => {}
+    }
+
+    tooltip
+}
+
+// every group shows its content once, followed by the locations of its copies
+fn generate_html_string_from_duplicates(duplicates: &[DuplicateGroup], asset_urls: &AssetUrls) -> String {
+    let mut html = String::with_capacity(1000);
+    html += &format!("<div class='project-area'>
+                        <div class='flex-center'>
+                            <div class='name-arrow-container' onclick='toggleProjectArea(event)'>
+                                <span class='down arrow-utf-8'>&#9660</span>
+                                <span class='up arrow-utf-8' style='display: none'>&#9650</span>
+                                <h1 class='title margin-right-05'>Duplicates</h1>
+                            </div>
+                            <span>({} groups of identical files, {} wasted)</span>
+                        </div>", duplicates.len(), format_bytes(get_total_wasted_bytes(duplicates)));
+
+    html += "<div class='duplicates-list'>\n";
+    for group in duplicates {
+        html += &format!("<div class='duplicate-group'>
+                <img src=\"{}\" alt=\"{}\" />
+                <div>
+                    <span class='duplicate-summary'>{} copies, {} each, {} wasted</span>
+                    <ul>", attr(&asset_urls.get_src(&group.locations[0].path)), attr(&group.content_hash),
+                group.locations.len(), format_bytes(group.size_bytes), format_bytes(group.get_wasted_bytes()));
+        for location in &group.locations {
+            html += &format!("<li title='{}' data-path='{}'><b>{}</b> {}</li>",
+                attr(&location.path), attr(&location.path), text(&location.project_name), text(&location.path));
+        }
+        html += "</ul></div></div>\n";
+    }
+    html.push_str("</div></div>\n");
+
+    html
+}
+
+// one list per project, with the file and line of every reference to an image that doesn't exist
+fn generate_html_string_from_broken_references(scan_result: &ScanResult) -> String {
+    let broken_count: usize = scan_result.projects.values().map(|project_dir| project_dir.broken_references.len()).sum();
+    let mut html = String::with_capacity(1000);
+    html += &format!("<div class='project-area'>
+                        <div class='flex-center'>
+                            <div class='name-arrow-container' onclick='toggleProjectArea(event)'>
+                                <span class='down arrow-utf-8'>&#9660</span>
+                                <span class='up arrow-utf-8' style='display: none'>&#9650</span>
+                                <h1 class='title margin-right-05'>Broken references</h1>
+                            </div>
+                            <span>({} references to images that don't exist)</span>
+                        </div>", broken_count);
+
+    html += "<div class='broken-references-list'>\n";
+    for project_dir in scan_result.projects.values().filter(|project_dir| !project_dir.broken_references.is_empty()) {
+        html += &format!("<b>{}</b><ul>", text(&project_dir.name));
+        for broken_reference in &project_dir.broken_references {
+            let reference = &broken_reference.reference;
+            let detail = match &broken_reference.kind {
+                BrokenReferenceKind::Missing => String::new(),
+                BrokenReferenceKind::CaseMismatch { actual_path } => format!(" <span class='broken-detail'>the file is {}</span>", text(actual_path)),
+            };
+            html += &format!("<li title='{}' data-path='{}'><span class='broken-kind'>{}</span> {}:{} <code>{}</code>{}</li>",
+                attr(&reference.snippet), attr(&reference.source_path), text(broken_reference.kind.get_name()),
+                text(&reference.source_path), reference.line, text(&reference.target), detail);
+        }
+        html += "</ul>\n";
+    }
+    html.push_str("</div></div>\n");
+
+    html
+}
+
+// every cluster is a row with its images side by side, so that they can be compared
+fn generate_html_string_from_similar_clusters(clusters: &[SimilarCluster], asset_urls: &AssetUrls) -> String {
+    let mut html = String::with_capacity(1000);
+    html += &format!("<div class='project-area'>
+                        <div class='flex-center'>
+                            <div class='name-arrow-container' onclick='toggleProjectArea(event)'>
+                                <span class='down arrow-utf-8'>&#9660</span>
+                                <span class='up arrow-utf-8' style='display: none'>&#9650</span>
+                                <h1 class='title margin-right-05'>Similar images</h1>
+                            </div>
+                            <span>({} groups of images that look alike)</span>
+                        </div>", clusters.len());
+
+    html += "<div class='similar-list'>\n";
+    for cluster in clusters {
+        html += &format!("<ul class='similar-cluster' title='{} images, hashes differ in up to {} bits'>",
+            cluster.images.len(), cluster.max_distance);
+        for image in &cluster.images {
+            html += &format!("<li title='{}' data-path='{}'> <img src=\"{}\" alt=\"{}\" /> <span>{}</span> <span class='similar-project'>{}</span> </li>",
+                attr(&image.path), attr(&image.path), attr(&asset_urls.get_src(&image.path)), attr(&image.name),
+                text(&image.name), text(&image.project_name));
+        }
+        html += "</ul>\n";
+    }
+    html.push_str("</div></div>\n");
+
+    html
+}
+
+fn generate_html_string_from_classes(icon_set: &IconSet, extension: &str) -> String {
+    let mut html = String::with_capacity(1000);
+    html += &format!("<div class='project-area'>
+                        <div class='flex-center'>
+                            <div class='name-arrow-container' onclick='toggleProjectArea(event)'>
+                                <span class='down arrow-utf-8'>&#9660</span>
+                                <span class='up arrow-utf-8' style='display: none'>&#9650</span>
+                                <h1 class='title margin-right-05'>{}</h1>
+                            </div>
+                            <span>({}) ---- class names are normally prefixed with `{}`{}</span>
+                        </div>", text(&icon_set.title), text(&icon_set.source_dir), text(&icon_set.class_prefix), get_style_families_description(icon_set));
+
+    html += "<ul class='images-area'>\n";
+    for glyph in &icon_set.glyphs {
+        let class = &glyph.class_names[0];
+        // the icons of another family, like the brands of font awesome, are not drawn by the font of the base class
+        let base_class = glyph.family.as_ref().unwrap_or(&icon_set.base_class);
+        let codepoint = glyph.get_codepoint_hex();
+        let mut tooltip = glyph.class_names.join(", ");
+        let mut search_text = glyph.class_names.join(" ");
+        if let Some(codepoint) = &codepoint {
+            tooltip += &format!("\nU+{}", codepoint.to_uppercase());
+            search_text += &format!(" U+{}", codepoint);
+        }
+        let alias_stamp = match glyph.class_names.len() {
+            1 => String::new(),
+            count => format!(" <span class='alias-stamp'>+{} alias{}</span>", count - 1, if count == 2 { "" } else { "es" }),
+        };
+        let copy_buttons = match &codepoint {
+            Some(codepoint) => format!(" <div class='glyph-copy'><button type='button' data-copy='\\{0}'>\\{0}</button><button type='button' data-copy='&amp;#x{0};'>&amp;#x{0};</button></div>",
+                codepoint),
+            None => String::new(),
+        };
+        html += &format!("<li class='image-container glyph' title='{}' data-search='{}'> <div class='extension-stamp color-{}'> <span>{}</span> </div> <i class='{} {}'></i> <span>{}</span>{}{} </li>
+            ", attr(&tooltip), attr(&search_text), attr(extension), text(extension), attr(base_class), attr(class),
+            text(class.strip_prefix(&icon_set.class_prefix).unwrap_or(class)), alias_stamp, copy_buttons);
+    }
+    html.push_str("</ul></div>\n");
+
+    html
+}
+
+fn get_style_families_description(icon_set: &IconSet) -> String {
+    if icon_set.style_families.is_empty() {
+        return String::new();
+    }
+    let style_families: Vec<String> = icon_set.style_families.iter().map(|family| format!("`{}`", text(family))).collect();
+    format!(", drawn with `{}` ---- styles: {}", text(&icon_set.base_class), style_families.join(", "))
+}
+
+// the urls of the icon sets that have a replacement in `asset_urls` are rewritten, like their fonts for the portable page
+fn get_css_string(icon_sets: &[IconSet], asset_urls: &AssetUrls) -> String {
+    let mut css = String::from("<style>
+        body {
+            background-color: #f1f1f1;
+            font-family: Arial, Helvetica, sans-serif;
+        }
+
+        #page-container {
+            position: relative;
+            min-height: 100vh;
+        }
+
+        ul {
+            padding: 0;
+            margin: 0;
+        }
+
+        .search-container {
+            position: sticky;
+            top: 0;
+            z-index: 1;
+            display: flex;
+            align-items: center;
+            column-gap: 3em;
+            box-shadow: 0 2px 4px rgba(0, 0, 0, 0.4);
+            background-color: #d3d3d3;
+            padding: 6px;
+        }
+
+        .search-container input {
+            font-size: 1em;
+            height: 1.6em;
+            padding: 0 0.3em;
+            border-radius: 3px;
+            border: none;
+            box-shadow: 0 0 3px rgba(0, 0, 0, 0.4);
+        }
+
+        .checkbox-item {
+            display: flex;
+            width: fit-content; 
+            width: -moz-fit-content;
+            align-items: center; 
+            column-gap: 0.2em;
+            cursor: pointer;
+        }
+
+        .checkbox-item ~ .checkbox-item {
+            margin-left: 0.8em;
+        }
+
+        .checkbox-item input {
+            width: 1em;
+            cursor: pointer;
+        }
+
+        .checkbox-item label {
+            cursor: pointer;
+        }
+
+        .date-marker-area span:first-child {
+            color: #3a3a3a;
+            font-size: 0.85em;
+            font-style: italic;
+        }
+
+        .date-marker-area span:last-child {
+            border: #fafafa; 
+            border-style: inset; 
+            padding: 0.1em; 
+        }
+
+        #copy-notification {
+            position: fixed; 
+            z-index: 1; 
+            right: 10; 
+            bottom: 15;
+            display: flex; 
+            align-items: center;
+            width: fit-content; 
+            width: -moz-fit-content;
+            background-color: rgba(0,0,0,0.8); 
+            border-radius: 16px; 
+        }
+
+        #copy-notification span:first-child {
+            color: white; 
+            padding: 0.5em 1em; 
+            font-style: italic;
+        }
+
+        .fade {
+            opacity: 0;
+            transition: opacity 0.3s ease-in-out;
+        }
+        
+        .fade.show {
+            opacity: 1;
+        }
+
+        .name-arrow-container {
+            cursor: pointer;
+            display: flex;
+            align-items: center;
+        }
+
+        .arrow-utf-8 {
+            font-size: 0.8em;
+            margin-top: 0.3em;
+            padding: 0.5em;
+        }
+
+        .arrow-utf-8.up {
+            font-size: 1em;
+            padding: 0.4em;
+        }
+
+        span.up.arrow-utf-8 {
+            color: #1fbc1f;
+        }
+
+        .sp-icons {
+            font-size: 2.5em;
+            margin-left: auto;
+            margin-right: auto;
+        }
+
+        .fa {
+            font-size: 2.5em !important;
+            margin-left: auto;
+            margin-right: auto;
+        }
+
+        .flex-center {
+            display: flex;
+            align-items: center;
+        }
+
+        .flex-1 {
+            flex: 1;
+        }
+
+        .margin-right-05 {
+            margin-right: 0.5em;
+        }
+
+        .project-area {
+            margin-bottom: 20px;
+        }
+
+        .title {
+            margin-top: 0.3em;
+            margin-bottom: 0.3em;
+        }
+
+        .title+span {
+            font-size: 0.9em;
+            color: #333333;
+        }
+
+        .images-area {
+            display: flex;
+            flex-wrap: wrap;
+            align-items: center;
+            column-gap: 10px;
+            row-gap: 6px;
+        }
+
+        .image-container  {
+            position: relative;
+            display: flex;
+            flex-direction: column;
+            word-wrap: break-word;
+            width: 4.5em;
+        }
+
+        .image-container[title] {
+            cursor: copy; 
+        }
+
+        .extension-stamp {
+            display: none;
+            width: 1.8em;
+            height: 1em;
+            border-radius: 50%;
+            z-index: 1;
+            top: -10;
+            left: -6;
+            position: absolute;
+        }
+
+        .extension-stamp > span {
+            color: black !important;
+        }
+
+        .image-container:hover > .extension-stamp {display: block; }
+
+        .color-svg {
+            background-color: #e971e9;
+        }
+
+        .color-png {
+            background-color: #71e98d;
+        }
+
+        .color-jpg, .color-jpeg {
+            background-color: #ff9ea5;
+        }
+
+        .color-gif {
+            background-color: #afefff;
+        }
+        
+        .color-ico {
+            background-color: #ffef00;
+        }
+        
+        .color-bmp {
+            background-color: #ff5656;
+        }
+
+        .image-container span {
+            display: block;
+            font-size: 0.8em;
+            text-align: center;
+            color: #818181;
+        }
+
+        .image-container img {
+            width: 3em;
+            height: auto;
+            max-height: 3.5em;
+            margin-left: auto;
+            margin-right: auto;
+        }
+        
+        .image-container.unused img {
+            opacity: 0.55;
+        }
+
+        .unused-stamp {
+            font-size: 0.7em;
+            font-style: italic;
+            color: #b35c00;
+        }
+
+        .new-stamp {
+            font-size: 0.7em;
+            font-weight: bold;
+            color: #1e7e34;
+        }
+
+        .image-container.glyph[title] {
+            cursor: default;
+        }
+
+        .image-container.glyph > i {
+            font-size: 2.5em !important;
+            margin-left: auto;
+            margin-right: auto;
+        }
+
+        .alias-stamp {
+            font-size: 0.7em;
+            font-style: italic;
+        }
+
+        .glyph-copy {
+            display: none;
+            position: absolute;
+            z-index: 2;
+            top: 100%;
+            left: -0.5em;
+            flex-direction: column;
+            row-gap: 2px;
+        }
+
+        .image-container.glyph:hover > .glyph-copy {
+            display: flex;
+        }
+
+        .glyph-copy button {
+            cursor: copy;
+            font-family: monospace;
+            font-size: 0.75em;
+            white-space: nowrap;
+        }
+
+        .image-container.new {
+            box-shadow: 0 0 0 2px #1e7e34;
+        }
+
+        .checkbox-item.only-new-item {
+            margin-left: 2em;
+        }
+
+        .details-panel {
+            position: fixed;
+            top: 4em;
+            right: 1em;
+            bottom: 1em;
+            width: min(36em, 45vw);
+            overflow-y: auto;
+            z-index: 10;
+            display: flex;
+            flex-direction: column;
+            row-gap: 6px;
+            padding: 1em;
+            background-color: #ffffff;
+            border: 1px solid #d0d0d0;
+            border-radius: 6px;
+            box-shadow: 0 4px 16px rgba(0, 0, 0, 0.15);
+        }
+
+        .details-header {
+            display: flex;
+            justify-content: space-between;
+            align-items: center;
+        }
+
+        .details-header button {
+            border: none;
+            background: none;
+            font-size: 1.1em;
+            cursor: pointer;
+        }
+
+        #details-name {
+            font-weight: bold;
+            word-break: break-all;
+        }
+
+        #details-image {
+            max-width: 6em;
+            max-height: 6em;
+        }
+
+        #details-path {
+            font-size: 0.85em;
+            color: #818181;
+            word-break: break-all;
+        }
+
+        #details-references {
+            display: flex;
+            flex-direction: column;
+            row-gap: 6px;
+        }
+
+        #details-references li {
+            list-style: none;
+            display: flex;
+            flex-direction: column;
+            font-size: 0.85em;
+        }
+
+        .details-location {
+            word-break: break-all;
+        }
+
+        #details-references code {
+            white-space: pre-wrap;
+            word-break: break-all;
+            color: #555555;
+            background-color: #f4f4f4;
+            padding: 2px 4px;
+        }
+
+        .duplicates-list {
+            display: flex;
+            flex-direction: column;
+            row-gap: 8px;
+        }
+
+        .duplicate-group {
+            display: flex;
+            align-items: center;
+            column-gap: 1em;
+        }
+
+        .duplicate-group > img {
+            width: 3em;
+            height: auto;
+            max-height: 3.5em;
+        }
+
+        .duplicate-group li {
+            list-style: none;
+            font-size: 0.85em;
+            color: #333333;
+            cursor: copy;
+        }
+
+        .duplicate-summary {
+            font-size: 0.9em;
+            font-style: italic;
+        }
+
+        .broken-references-list li {
+            list-style: none;
+            font-size: 0.85em;
+            color: #333333;
+            cursor: copy;
+            padding: 2px 0;
+        }
+
+        .broken-kind {
+            display: inline-block;
+            min-width: 7em;
+            font-style: italic;
+            color: #c0392b;
+        }
+
+        .broken-detail {
+            color: #818181;
+        }
+
+        .similar-list {
+            display: flex;
+            flex-direction: column;
+            row-gap: 8px;
+        }
+
+        .similar-cluster {
+            display: flex;
+            flex-wrap: wrap;
+            align-items: flex-end;
+            column-gap: 1.5em;
+            padding: 6px 0;
+            border-bottom: 1px solid #e0e0e0;
+        }
+
+        .similar-cluster li {
+            list-style: none;
+            display: flex;
+            flex-direction: column;
+            align-items: center;
+            font-size: 0.85em;
+            cursor: copy;
+        }
+
+        .similar-cluster img {
+            width: 4em;
+            height: auto;
+            max-height: 4.5em;
+        }
+
+        .similar-project {
+            color: #818181;
+            font-style: italic;
+        }
+
+        footer.author-area {
+            position: absolute;
+            bottom: 0;
+            right: 0;
+            display: flex;
+            justify-content: right;
+        }
+
+        footer.author-area span {
+            color: #818181;
+            font-size: 0.9em;
+            font-style: italic;
+            margin-right: 0.5em;
+        }
+
+        \n\n");
+    for icon_set in icon_sets {
+        if !icon_set.css.is_empty() {
+            css += &format!("/*===================>  {} AREA <===================*/\n\n", icon_set.title.to_uppercase().replace("*/", ""));
+            css += &escape_style_content(&rewrite_css_urls(&icon_set.css, |path| asset_urls.urls.get(path).cloned()));
+        }
+    }
+    css += "</style>";
+
+    css
+}
+
@@ -0,0 +1,465 @@
+use std::{fs::File, io::{self, Read, Seek, SeekFrom}, path::Path};
+
+// How much of an svg file is searched for the root <svg> tag. Editors put at most a prolog, a doctype and a comment before it.
+const SVG_HEADER_MAX_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    Png,
+    Gif,
+    Bmp,
+    Jpeg,
+    Ico,
+    Svg,
+}
+
+impl ImageFormat {
+    pub const ALL: &'static [ImageFormat] = &[ImageFormat::Png, ImageFormat::Gif, ImageFormat::Bmp, ImageFormat::Jpeg, ImageFormat::Ico, ImageFormat::Svg];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ImageFormat::Png  => "png",
+            ImageFormat::Gif  => "gif",
+            ImageFormat::Bmp  => "bmp",
+            ImageFormat::Jpeg => "jpeg",
+            ImageFormat::Ico  => "ico",
+            ImageFormat::Svg  => "svg",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|format| format.get_name() == name)
+    }
+
+    // whether a file with this extension is expected to be of this format
+    pub fn matches_extension(&self, extension: &str) -> bool {
+        match self {
+            ImageFormat::Jpeg => extension == "jpg" || extension == "jpeg",
+            ImageFormat::Ico  => extension == "ico" || extension == "cur",
+            _ => extension == self.get_name(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dimensions {
+    pub width: u32,
+    pub height: u32,
+}
+
+// What could be learned from the first bytes of an image file, without decoding it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImageHeader {
+    // the format that the content actually has, regardless of the extension. None if it is not recognised.
+    pub format: Option<ImageFormat>,
+    // pixel dimensions, for an ico file the ones of its largest entry. None if the header is malformed or,
+    // for svg files, if the size is not given in pixels.
+    pub dimensions: Option<Dimensions>,
+}
+
+// Reads just enough of the file to tell its format and dimensions. Io errors are returned, malformed headers are not errors.
+pub fn read_image_header(path: &Path) -> io::Result<ImageHeader> {
+    let mut file = File::open(path)?;
+    let mut magic = [0u8; 32];
+    let magic_len = read_up_to(&mut file, &mut magic)?;
+    let magic = &magic[..magic_len];
+
+    let header = match sniff_binary_format(magic) {
+        Some(format) => {
+            file.seek(SeekFrom::Start(0))?;
+            let dimensions = match format {
+                ImageFormat::Png  => read_png_dimensions(magic),
+                ImageFormat::Gif  => read_gif_dimensions(magic),
+                ImageFormat::Bmp  => read_bmp_dimensions(magic),
+                ImageFormat::Jpeg => read_jpeg_dimensions(&mut file)?,
+                ImageFormat::Ico  => read_ico_dimensions(&mut file)?,
+                ImageFormat::Svg  => None,
+            };
+            ImageHeader { format: Some(format), dimensions }
+        }
+        None if looks_like_text(magic) => {
+            let content = read_svg_header(&mut file)?;
+            match find_svg_tag(&content) {
+                Some(svg_tag) => ImageHeader { format: Some(ImageFormat::Svg), dimensions: parse_svg_dimensions(svg_tag) },
+                None => ImageHeader::default(),
+            }
+        }
+        None => ImageHeader::default(),
+    };
+
+    Ok(header)
+}
+
+fn sniff_binary_format(magic: &[u8]) -> Option<ImageFormat> {
+    if magic.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(ImageFormat::Png)
+    } else if magic.starts_with(b"GIF87a") || magic.starts_with(b"GIF89a") {
+        Some(ImageFormat::Gif)
+    } else if magic.starts_with(b"BM") && magic.len() >= 26 {
+        Some(ImageFormat::Bmp)
+    } else if magic.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some(ImageFormat::Jpeg)
+    } else if magic.starts_with(&[0, 0, 1, 0]) || magic.starts_with(&[0, 0, 2, 0]) {
+        Some(ImageFormat::Ico)
+    } else {
+        None
+    }
+}
+
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match reader.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(total)
+}
+
+fn looks_like_text(magic: &[u8]) -> bool {
+    let magic = magic.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(magic);
+    magic.iter().all(|b| b.is_ascii_whitespace() || b.is_ascii_graphic() || *b >= 0x80)
+}
+
+fn be_u16(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as u32)
+}
+
+fn le_u16(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as u32)
+}
+
+fn be_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn le_i32(bytes: &[u8], at: usize) -> Option<i32> {
+    bytes.get(at..at + 4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn make_dimensions(width: u32, height: u32) -> Option<Dimensions> {
+    if width == 0 || height == 0 {
+        None
+    } else {
+        Some(Dimensions { width, height })
+    }
+}
+
+// signature (8), IHDR length (4), "IHDR" (4), width (4 BE), height (4 BE)
+fn read_png_dimensions(magic: &[u8]) -> Option<Dimensions> {
+    if magic.get(12..16) != Some(b"IHDR") {
+        return None;
+    }
+    make_dimensions(be_u32(magic, 16)?, be_u32(magic, 20)?)
+}
+
+// signature (6), logical screen width (2 LE), logical screen height (2 LE)
+fn read_gif_dimensions(magic: &[u8]) -> Option<Dimensions> {
+    make_dimensions(le_u16(magic, 6)?, le_u16(magic, 8)?)
+}
+
+// file header (14), then the DIB header that starts with its own size. The old OS/2 header has 16 bit sizes,
+// every later one has signed 32 bit sizes, where a negative height means a top-down bitmap.
+fn read_bmp_dimensions(magic: &[u8]) -> Option<Dimensions> {
+    let dib_header_size = le_i32(magic, 14)?;
+    if dib_header_size == 12 {
+        make_dimensions(le_u16(magic, 18)?, le_u16(magic, 20)?)
+    } else {
+        make_dimensions(le_i32(magic, 18)?.unsigned_abs(), le_i32(magic, 22)?.unsigned_abs())
+    }
+}
+
+// Walks the segments until a start-of-frame one, skipping the (possibly big) exif and icc segments without reading them.
+fn read_jpeg_dimensions(file: &mut File) -> io::Result<Option<Dimensions>> {
+    let mut reader = io::BufReader::new(file);
+    let mut soi = [0u8; 2];
+    reader.read_exact(&mut soi)?;
+
+    loop {
+        let mut byte = [0u8; 1];
+        // markers can be padded with any number of 0xFF bytes
+        if read_up_to(&mut reader, &mut byte)? == 0 || byte[0] != 0xFF {
+            return Ok(None);
+        }
+        let mut marker = 0xFF;
+        while marker == 0xFF {
+            if read_up_to(&mut reader, &mut byte)? == 0 {
+                return Ok(None);
+            }
+            marker = byte[0];
+        }
+
+        // standalone markers, without a length
+        if marker == 0x01 || (0xD0..=0xD8).contains(&marker) {
+            continue;
+        }
+        // end of image or start of scan, the frame header should have come before them
+        if marker == 0xD9 || marker == 0xDA {
+            return Ok(None);
+        }
+
+        let mut length = [0u8; 2];
+        if read_up_to(&mut reader, &mut length)? < 2 {
+            return Ok(None);
+        }
+        let length = u16::from_be_bytes(length) as i64;
+        if length < 2 {
+            return Ok(None);
+        }
+
+        // SOF0 - SOF15, except DHT (C4), JPG (C8) and DAC (CC) that share the range
+        if (0xC0..=0xCF).contains(&marker) && marker != 0xC4 && marker != 0xC8 && marker != 0xCC {
+            let mut frame = [0u8; 5];
+            if read_up_to(&mut reader, &mut frame)? < 5 {
+                return Ok(None);
+            }
+            // precision (1), height (2 BE), width (2 BE)
+            return Ok(be_u16(&frame, 3).zip(be_u16(&frame, 1)).and_then(|(width, height)| make_dimensions(width, height)));
+        }
+
+        reader.seek_relative(length - 2)?;
+    }
+}
+
+// reserved (2), type (2), count (2 LE), then 16 byte entries that start with a width and a height byte, where 0 means 256
+fn read_ico_dimensions(file: &mut File) -> io::Result<Option<Dimensions>> {
+    let mut header = [0u8; 6];
+    if read_up_to(file, &mut header)? < 6 {
+        return Ok(None);
+    }
+    let count = le_u16(&header, 4).unwrap_or(0) as usize;
+    let mut entries = vec![0u8; count * 16];
+    let entries_len = read_up_to(file, &mut entries)?;
+
+    let largest = entries[..entries_len]
+        .chunks_exact(16)
+        .map(|entry| {
+            let width = if entry[0] == 0 { 256 } else { entry[0] as u32 };
+            let height = if entry[1] == 0 { 256 } else { entry[1] as u32 };
+            (width, height)
+        })
+        .max_by_key(|(width, height)| width * height);
+
+    Ok(largest.and_then(|(width, height)| make_dimensions(width, height)))
+}
+
+fn read_svg_header(file: &mut File) -> io::Result<String> {
+    file.seek(SeekFrom::Start(0))?;
+    let mut bytes = Vec::with_capacity(4096);
+    file.take(SVG_HEADER_MAX_BYTES).read_to_end(&mut bytes)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// returns the attributes part of the root <svg ...> tag, skipping the comments that could contain another one
+fn find_svg_tag(content: &str) -> Option<&str> {
+    let mut search_from = 0;
+    while let Some(index) = content[search_from..].find("<svg") {
+        let comment_start = content[search_from..].find("<!--").map(|comment_index| search_from + comment_index);
+        if let Some(comment_start) = comment_start.filter(|&comment_start| comment_start < search_from + index) {
+            search_from = comment_start + 4 + content[comment_start + 4..].find("-->")? + 3;
+            continue;
+        }
+        let tag_start = search_from + index + 4;
+        let next = content[tag_start..].chars().next();
+        if matches!(next, Some(c) if c.is_whitespace() || c == '>' || c == '/') {
+            let tag_end = tag_start + content[tag_start..].find('>')?;
+            return Some(&content[tag_start..tag_end]);
+        }
+        search_from = tag_start;
+    }
+    None
+}
+
+// Uses `width` and `height` when they are given in pixels, falling back to the `viewBox` size (or its aspect ratio,
+// when only one of them is given). Percentages and physical units can't be known without a viewport.
+pub fn parse_svg_dimensions(svg_tag_attributes: &str) -> Option<Dimensions> {
+    let width = get_xml_attribute(svg_tag_attributes, "width").and_then(parse_svg_length);
+    let height = get_xml_attribute(svg_tag_attributes, "height").and_then(parse_svg_length);
+    let view_box = get_xml_attribute(svg_tag_attributes, "viewBox").and_then(|value| {
+        let numbers: Vec<f64> = value
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|part| !part.is_empty())
+            .map(|part| part.parse::<f64>())
+            .collect::<Result<_, _>>()
+            .ok()?;
+        match numbers[..] {
+            [_, _, width, height] if width > 0.0 && height > 0.0 => Some((width, height)),
+            _ => None,
+        }
+    });
+
+    let (width, height) = match (width, height, view_box) {
+        (Some(width), Some(height), _) => (width, height),
+        (Some(width), None, Some((vb_width, vb_height))) => (width, width * vb_height / vb_width),
+        (None, Some(height), Some((vb_width, vb_height))) => (height * vb_width / vb_height, height),
+        (None, None, Some(view_box)) => view_box,
+        _ => return None,
+    };
+
+    make_dimensions(width.round() as u32, height.round() as u32)
+}
+
+fn parse_svg_length(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let number = value.strip_suffix("px").unwrap_or(value).trim();
+    number.parse::<f64>().ok().filter(|n| n.is_finite() && *n > 0.0)
+}
+
+fn get_xml_attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut search_from = 0;
+    while let Some(index) = attributes[search_from..].find(name) {
+        let name_start = search_from + index;
+        let name_end = name_start + name.len();
+        search_from = name_end;
+
+        // it should be a whole attribute name, not the end of `stroke-width` or the start of `widths`
+        let preceded_by_space = attributes[..name_start].chars().next_back().is_none_or(|c| c.is_whitespace());
+        if !preceded_by_space {
+            continue;
+        }
+        let rest = attributes[name_end..].trim_start();
+        let Some(rest) = rest.strip_prefix('=') else { continue };
+        let rest = rest.trim_start();
+        let Some(quote) = rest.chars().next() else { continue };
+        if quote != '"' && quote != '\'' {
+            continue;
+        }
+        let value = &rest[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn read_header(name: &str, bytes: &[u8]) -> ImageHeader {
+        let path = std::env::temp_dir().join(format!("img-dumper-header-{}-{}", std::process::id(), name));
+        fs::write(&path, bytes).unwrap();
+        let header = read_image_header(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        header
+    }
+
+    fn dimensions(width: u32, height: u32) -> Option<Dimensions> {
+        Some(Dimensions { width, height })
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        bytes.extend(width.to_be_bytes());
+        bytes.extend(height.to_be_bytes());
+        bytes.extend([8, 6, 0, 0, 0]);
+        bytes
+    }
+
+    fn bmp(dib_header: &[u8]) -> Vec<u8> {
+        let mut bytes = b"BM".to_vec();
+        bytes.extend([0; 12]);
+        bytes.extend(dib_header);
+        bytes
+    }
+
+    // an APP0 segment that the frame header follows, after some fill bytes
+    fn jpeg(width: u16, height: u16) -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xD8, 0xFF, 0xE0, 0, 16];
+        bytes.extend(b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+        bytes.extend([0xFF, 0xFF, 0xC0, 0, 17, 8]);
+        bytes.extend(height.to_be_bytes());
+        bytes.extend(width.to_be_bytes());
+        bytes.extend([3; 10]);
+        bytes
+    }
+
+    fn ico(sizes: &[(u8, u8)]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, 1, 0];
+        bytes.extend((sizes.len() as u16).to_le_bytes());
+        for (width, height) in sizes {
+            bytes.extend([*width, *height]);
+            bytes.extend([0; 14]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn binary_headers_give_their_format_and_dimensions() {
+        assert_eq!(read_header("png", &png(640, 480)), ImageHeader { format: Some(ImageFormat::Png), dimensions: dimensions(640, 480) });
+        assert_eq!(read_header("gif", b"GIF89a\x20\x03\x58\x02\0\0\0"),
+            ImageHeader { format: Some(ImageFormat::Gif), dimensions: dimensions(800, 600) });
+
+        let mut info_header = vec![40, 0, 0, 0];
+        info_header.extend(300i32.to_le_bytes());
+        // negative for a top-down bitmap
+        info_header.extend((-200i32).to_le_bytes());
+        assert_eq!(read_header("bmp", &bmp(&info_header)).dimensions, dimensions(300, 200));
+        let os2_header = [12, 0, 0, 0, 0x2C, 0x01, 0xC8, 0x00, 1, 0, 24, 0];
+        assert_eq!(read_header("bmp-os2", &bmp(&os2_header)).dimensions, dimensions(300, 200));
+
+        assert_eq!(read_header("jpeg", &jpeg(1920, 1080)), ImageHeader { format: Some(ImageFormat::Jpeg), dimensions: dimensions(1920, 1080) });
+        // a 0 size byte is 256, and the largest entry is the one that counts
+        assert_eq!(read_header("ico", &ico(&[(16, 16), (0, 0), (48, 48)])), ImageHeader { format: Some(ImageFormat::Ico), dimensions: dimensions(256, 256) });
+    }
+
+    #[test]
+    fn corrupt_headers_have_no_dimensions() {
+        let mut png_without_ihdr = png(640, 480);
+        png_without_ihdr[12..16].copy_from_slice(b"IDAT");
+        assert_eq!(read_header("png-no-ihdr", &png_without_ihdr), ImageHeader { format: Some(ImageFormat::Png), dimensions: None });
+        assert_eq!(read_header("png-zero", &png(0, 480)).dimensions, None);
+
+        // the scan starts before any frame header
+        assert_eq!(read_header("jpeg-sos", &[0xFF, 0xD8, 0xFF, 0xDA, 0, 2, 0xFF, 0xC0, 0, 17]).dimensions, None);
+        // a segment length that is shorter than the length itself
+        assert_eq!(read_header("jpeg-length", &[0xFF, 0xD8, 0xFF, 0xE0, 0, 1, 0xFF, 0xC0]).dimensions, None);
+        // a length that points past the end of the file
+        assert_eq!(read_header("jpeg-past-end", &[0xFF, 0xD8, 0xFF, 0xE0, 0xFF, 0xFF, 0]).dimensions, None);
+
+        // 0xFFFF entries announced, none there
+        assert_eq!(read_header("ico-count", &[0, 0, 1, 0, 0xFF, 0xFF]), ImageHeader { format: Some(ImageFormat::Ico), dimensions: None });
+        assert_eq!(read_header("not-an-image", b"just some text"), ImageHeader::default());
+    }
+
+    #[test]
+    fn truncated_headers_do_not_panic() {
+        let mut info_header = vec![40, 0, 0, 0];
+        info_header.extend(300i32.to_le_bytes());
+        info_header.extend(200i32.to_le_bytes());
+        let fixtures = [
+            (png(640, 480), 640, 480),
+            (b"GIF89a\x20\x03\x58\x02".to_vec(), 800, 600),
+            (bmp(&info_header), 300, 200),
+            (jpeg(1920, 1080), 1920, 1080),
+            (ico(&[(16, 16), (32, 32)]), 32, 32),
+        ];
+        for (index, (bytes, width, height)) in fixtures.iter().enumerate() {
+            for length in 0..bytes.len() {
+                let header = read_header(&format!("truncated-{}-{}", index, length), &bytes[..length]);
+                // the first entries of an ico are enough for their own size
+                let is_possible = header.dimensions.is_none_or(|dimensions| dimensions.width <= *width && dimensions.height <= *height);
+                assert!(is_possible, "{} bytes of fixture {}: {:?}", length, index, header);
+            }
+        }
+    }
+
+    #[test]
+    fn svg_dimensions_come_from_the_root_tag() {
+        let read_svg = |name: &str, svg: &str| read_header(name, svg.as_bytes());
+        assert_eq!(read_svg("svg-view-box", "<?xml version=\"1.0\"?>\n<!-- <svg width=\"1\"> -->\n<svg viewBox=\"0 0 24 16\"><path/></svg>"),
+            ImageHeader { format: Some(ImageFormat::Svg), dimensions: dimensions(24, 16) });
+        assert_eq!(read_svg("svg-px", "<svg stroke-width='3' width='32px' height='20'/>").dimensions, dimensions(32, 20));
+        // the height follows the aspect ratio of the view box
+        assert_eq!(read_svg("svg-width", "<svg width=\"48\" viewBox=\"0,0,24,16\">").dimensions, dimensions(48, 32));
+        assert_eq!(read_svg("svg-percent", "<svg width=\"100%\" height=\"100%\">").dimensions, None);
+        assert_eq!(read_svg("svg-bad-view-box", "<svg viewBox=\"0 0 24\">").dimensions, None);
+        // an attribute without a value does not hide the others
+        assert_eq!(read_svg("svg-no-value", "<svg viewBox=\"0 0 24 16\" width=>").dimensions, dimensions(24, 16));
+        assert_eq!(read_svg("svg-unclosed", "<svg viewBox=\"0 0 24 16\"").format, None);
+        assert_eq!(read_svg("svgs", "<svgs viewBox=\"0 0 24 16\">").format, None);
+    }
+}
@@ -0,0 +1,286 @@
+use serde::Serialize;
+
+use crate::{portable::make_snapshot_thumbnails, references::BrokenReferenceKind, renderer::Renderer, similarity::format_perceptual_hash,
+    IconSet, Img, ProjectDir, ScanResult, VERSION_ID};
+
+// Bumped whenever a field is removed, renamed or changes meaning. Adding new fields does not bump it,
+// so consumers should ignore the fields they don't know.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+pub struct JsonRenderer;
+
+impl Renderer for JsonRenderer {
+    fn get_extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn render(&self, scan_result: &ScanResult) -> anyhow::Result<String> {
+        generate_json_as_string(scan_result)
+    }
+}
+
+// Root object of the json export (schema version 1):
+// {
+//   "schema_version": 1,
+//   "generator": { "name": "img-dumper", "version": "v1.0.0" },
+//   "generated_at": "2023-06-01T12:00:00+03:00",      RFC 3339, local time of the scan
+//   "root_dir": "/opt/lampp/htdocs",
+//   "projects": [                                     sorted by name, only projects with images or broken references
+//     { "name": "...", "path": "...", "images": [     images sorted by name
+//         { "name": "logo", "path": "/opt/lampp/htdocs/p/logo.svg", "extension": "svg",
+//           "size_bytes": 1234, "modified": "2023-05-30T09:00:00+03:00",  modified is null if unknown
+//           "format": "svg", "width": 24, "height": 24,                   sniffed from the header, null if unknown
+//           "content_hash": "af13...",                                    blake3 of the content, null if unreadable
+//           "perceptual_hash": "8f0e1c3c3c1c0e0f",                        dHash of the pixels as 16 hex digits,
+//                                                                          null unless similar images were searched for
+//           "thumbnail": "data:image/png;base64,...",                     at most 64px, for the diff of a later scan,
+//                                                                          null if it can't be decoded and in the query api
+//           "references": [                                                the source files that point at the image,
+//             { "source_path": "...", "line": 12, "target": "img/logo.svg", "snippet": "<img src=..." } ] } ] } ],
+//                                                                          empty when unused, null unless searched for
+//       "broken_references": [                        references to images that don't exist, empty unless searched for
+//         { "source_path": "...", "line": 3, "target": "img/Logo.png", "snippet": "...",
+//           "kind": "case_mismatch", "actual_path": "/opt/lampp/htdocs/p/img/logo.png" } ] } ],  kind is missing or case_mismatch
+//   "icon_sets": [                                    only the sets that were found and parsed
+//     { "id": "font-awesome", "title": "font-awesome", "source_dir": "...", "base_class": "fa-solid",
+//       "style_families": ["fa-solid", "fa-regular", "fa-brands"], "class_names": ["fa-bars", "fa-navicon", ...],  may be empty
+//       "glyphs": [ { "class_names": ["fa-bars", "fa-navicon"], "codepoint": "f0c9", "family": null } ] } ]
+//                     aliases after the first name    null unless a single character    null for the base class
+// }
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    schema_version: u32,
+    generator: JsonGenerator,
+    generated_at: String,
+    root_dir: &'a str,
+    projects: Vec<JsonProject<'a>>,
+    icon_sets: Vec<JsonIconSet<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonGenerator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+struct JsonProject<'a> {
+    name: &'a str,
+    path: &'a str,
+    images: Vec<JsonImage<'a>>,
+    broken_references: Vec<JsonBrokenReference<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonBrokenReference<'a> {
+    source_path: &'a str,
+    line: usize,
+    target: &'a str,
+    snippet: &'a str,
+    kind: &'static str,
+    actual_path: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonImage<'a> {
+    name: &'a str,
+    path: &'a str,
+    extension: &'a str,
+    size_bytes: u64,
+    modified: Option<String>,
+    format: Option<&'static str>,
+    width: Option<u32>,
+    height: Option<u32>,
+    content_hash: Option<&'a str>,
+    perceptual_hash: Option<String>,
+    thumbnail: Option<String>,
+    references: Option<Vec<JsonReference<'a>>>,
+}
+
+#[derive(Serialize)]
+struct JsonReference<'a> {
+    source_path: &'a str,
+    line: usize,
+    target: &'a str,
+    snippet: &'a str,
+}
+
+#[derive(Serialize)]
+struct JsonIconSet<'a> {
+    id: &'a str,
+    title: &'a str,
+    source_dir: &'a str,
+    base_class: &'a str,
+    style_families: &'a [String],
+    class_names: &'a [String],
+    glyphs: Vec<JsonGlyph<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonGlyph<'a> {
+    class_names: &'a [String],
+    codepoint: Option<String>,
+    family: Option<&'a str>,
+}
+
+impl<'a> From<&'a Img> for JsonImage<'a> {
+    fn from(img: &'a Img) -> Self {
+        Self {
+            name: &img.name,
+            path: &img.path,
+            extension: &img.extension,
+            size_bytes: img.size_bytes,
+            modified: img.modified.map(|modified| modified.to_rfc3339()),
+            format: img.header.format.map(|format| format.get_name()),
+            width: img.header.dimensions.map(|dimensions| dimensions.width),
+            height: img.header.dimensions.map(|dimensions| dimensions.height),
+            content_hash: img.content_hash.as_deref(),
+            perceptual_hash: img.perceptual_hash.map(format_perceptual_hash),
+            thumbnail: None,
+            references: img.references.as_ref().map(|references| references.iter()
+                .map(|reference| JsonReference {
+                    source_path: &reference.source_path,
+                    line: reference.line,
+                    target: &reference.target,
+                    snippet: &reference.snippet,
+                })
+                .collect()),
+        }
+    }
+}
+
+impl<'a> From<&'a ProjectDir> for JsonProject<'a> {
+    fn from(project_dir: &'a ProjectDir) -> Self {
+        Self {
+            name: &project_dir.name,
+            path: &project_dir.path,
+            images: project_dir.images.iter().map(JsonImage::from).collect(),
+            broken_references: project_dir.broken_references.iter()
+                .map(|broken_reference| JsonBrokenReference {
+                    source_path: &broken_reference.reference.source_path,
+                    line: broken_reference.reference.line,
+                    target: &broken_reference.reference.target,
+                    snippet: &broken_reference.reference.snippet,
+                    kind: match broken_reference.kind {
+                        BrokenReferenceKind::Missing => "missing",
+                        BrokenReferenceKind::CaseMismatch { .. } => "case_mismatch",
+                    },
+                    actual_path: match &broken_reference.kind {
+                        BrokenReferenceKind::Missing => None,
+                        BrokenReferenceKind::CaseMismatch { actual_path } => Some(actual_path),
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a IconSet> for JsonIconSet<'a> {
+    fn from(icon_set: &'a IconSet) -> Self {
+        Self {
+            id: &icon_set.title,
+            title: &icon_set.title,
+            source_dir: &icon_set.source_dir,
+            base_class: &icon_set.base_class,
+            style_families: &icon_set.style_families,
+            class_names: &icon_set.class_names,
+            glyphs: icon_set.glyphs.iter()
+                .map(|glyph| JsonGlyph { class_names: &glyph.class_names, codepoint: glyph.get_codepoint_hex(), family: glyph.family.as_deref() })
+                .collect(),
+        }
+    }
+}
+
+pub fn generate_json_as_string(scan_result: &ScanResult) -> anyhow::Result<String> {
+    let mut thumbnails = make_snapshot_thumbnails(&scan_result.projects);
+    let mut export = JsonExport {
+        schema_version: JSON_SCHEMA_VERSION,
+        generator: JsonGenerator { name: "img-dumper", version: VERSION_ID },
+        generated_at: scan_result.scanned_at.to_rfc3339(),
+        root_dir: &scan_result.root_dir,
+        projects: scan_result.projects.values().map(JsonProject::from).collect(),
+        icon_sets: scan_result.icon_sets.iter()
+            .filter(|icon_set| !icon_set.class_names.is_empty())
+            .map(JsonIconSet::from)
+            .collect(),
+    };
+    for image in export.projects.iter_mut().flat_map(|project| project.images.iter_mut()) {
+        image.thumbnail = thumbnails.remove(image.path);
+    }
+
+    let mut json = serde_json::to_string_pretty(&export)?;
+    json.push('\n');
+    Ok(json)
+}
+
+// The filters of the `/api/images` endpoint of the local server. A filter that is not given matches every image.
+#[derive(Debug, Default)]
+pub struct ImageQuery {
+    // case insensitive part of the image name
+    pub text: Option<String>,
+    // lowercase extensions without the dot
+    pub extensions: Vec<String>,
+    // exact project name
+    pub project: Option<String>,
+}
+
+impl ImageQuery {
+    pub fn matches(&self, project_dir: &ProjectDir, img: &Img) -> bool {
+        self.text.as_ref().is_none_or(|text| img.name.to_lowercase().contains(&text.to_lowercase()))
+            && (self.extensions.is_empty() || self.extensions.contains(&img.extension.to_lowercase()))
+            && self.project.as_ref().is_none_or(|project| *project == project_dir.name)
+    }
+}
+
+#[derive(Serialize)]
+struct JsonProjectSummary<'a> {
+    name: &'a str,
+    path: &'a str,
+    image_count: usize,
+    size_bytes: u64,
+    broken_reference_count: usize,
+}
+
+#[derive(Serialize)]
+struct JsonProjectImage<'a> {
+    project: &'a str,
+    #[serde(flatten)]
+    image: JsonImage<'a>,
+}
+
+// `[{ "name": "...", "path": "...", "image_count": 12, "size_bytes": 34567, "broken_reference_count": 0 }]`
+pub fn generate_projects_api_json(scan_result: &ScanResult) -> anyhow::Result<String> {
+    let projects: Vec<JsonProjectSummary> = scan_result.projects.values()
+        .map(|project_dir| JsonProjectSummary {
+            name: &project_dir.name,
+            path: &project_dir.path,
+            image_count: project_dir.images.len(),
+            size_bytes: project_dir.images.iter().map(|img| img.size_bytes).sum(),
+            broken_reference_count: project_dir.broken_references.len(),
+        })
+        .collect();
+    Ok(serde_json::to_string(&projects)?)
+}
+
+// The images of the export that match the query, each with a `project` field: `[{ "project": "...", "name": "...", ... }]`
+pub fn generate_images_api_json(scan_result: &ScanResult, query: &ImageQuery) -> anyhow::Result<String> {
+    let images: Vec<JsonProjectImage> = scan_result.projects.values()
+        .flat_map(|project_dir| project_dir.images.iter()
+            .filter(|img| query.matches(project_dir, img))
+            .map(|img| JsonProjectImage { project: &project_dir.name, image: JsonImage::from(img) }))
+        .collect();
+    Ok(serde_json::to_string(&images)?)
+}
+
+// The icon sets of the export, or only the one with the given id. None when no parsed set has that id.
+pub fn generate_icons_api_json(scan_result: &ScanResult, set_id: Option<&str>) -> anyhow::Result<Option<String>> {
+    let icon_sets: Vec<JsonIconSet> = scan_result.icon_sets.iter()
+        .filter(|icon_set| !icon_set.class_names.is_empty())
+        .filter(|icon_set| set_id.is_none_or(|set_id| icon_set.title == set_id))
+        .map(JsonIconSet::from)
+        .collect();
+    if set_id.is_some() && icon_sets.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::to_string(&icon_sets)?))
+}
@@ -0,0 +1,27 @@
+pub mod bundle;
+pub mod css_parser;
+pub mod csv_report;
+pub mod diff_report;
+pub mod duplicates;
+pub mod history;
+pub mod html_escape;
+pub mod html_report;
+pub mod image_metadata;
+pub mod json_export;
+pub mod markdown_report;
+pub mod portable;
+pub mod references;
+pub mod renderer;
+pub mod scan_cache;
+pub mod scan_diff;
+pub mod scanner;
+pub mod similarity;
+pub mod special_files;
+pub mod utils;
+
+pub use renderer::{OutputFormat, Renderer};
+pub use scanner::{Img, ProjectDir, ScanResult, Scanner};
+pub use special_files::{IconSet, ParsableFileSpec};
+
+// Application version, to be displayed at startup and on the webpage
+pub const VERSION_ID : &str = "v1.0.0"; 
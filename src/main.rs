@@ -1,17 +1,25 @@
+mod app_config;
+mod cli;
+mod config_file;
 mod message_printer;
+mod server;
+mod watcher;
 
-use std::{fs::File, io::{Write, BufReader, BufRead}, collections::HashMap, process::Command, path::{PathBuf, Path}, env, time::Instant};
-use std::fmt::Debug;
+use std::{collections::BTreeMap, fs::File, io::Write, process::Command, time::Instant};
 
 use anyhow::{Context, anyhow};
 use colored::*;
-use chrono::{DateTime, Local};
-use walkdir::WalkDir;
 
-use message_printer::*;
+use img_dumper::{bundle::write_bundle, diff_report::generate_diff_html_page_as_string, duplicates::get_total_wasted_bytes, history::{count_new_images, mark_new_images, save_snapshot},
+    references::{get_unused_images, BrokenReferenceKind},
+    html_report::HtmlRenderer, scan_diff::{diff_snapshots, ImageChange, Snapshot}, utils::format_bytes, OutputFormat, ProjectDir, Renderer, ScanResult, Scanner, VERSION_ID};
 
-// Application version, to be displayed at startup and on the webpage
-pub const VERSION_ID : &str = "v1.0.0"; 
+use app_config::AppConfig;
+use cli::{CommandLineArgs, SubCommand};
+use message_printer::{status, statusln};
+
+// most mail servers reject bigger attachments
+const PORTABLE_SIZE_WARNING_BYTES: u64 = 20 * 1024 * 1024;
 
 fn main() -> anyhow::Result<()> {
     let instant = Instant::now();
@@ -20,1039 +28,342 @@ fn main() -> anyhow::Result<()> {
     #[cfg(target_os = "windows")]
     control::set_virtual_terminal(true).unwrap();
 
-    println!("img-dumper {}\n", VERSION_ID);
-
-    let program_args = parse_args()?;
-    let program_args = match program_args {
+    let program_args = match cli::parse_args()? {
         Some(value) => value,
-        None => {
-            message_printer::print_whole_help_message();
-            return Ok(());
-        }
+        None => return Ok(()),
     };
-
-    let mut app_config = AppConfig::init(program_args)?;
-    println!("Root folder: {}\n", app_config.root_dir);
-
-    print!("Parsing projects... ");
-    let mut projects_map = traverse_root_dir_and_make_project_map(&app_config);
-    projects_map.retain(|_, project_dir| !project_dir.images.is_empty());
-    if projects_map.is_empty() {
-        println!("{}", "No icons could be found for any projects".yellow());
-    } else {
-        println!("{} ({} found)", "OK".green(), projects_map.len());
+    message_printer::set_status_to_stderr(program_args.output.as_deref() == Some("-"));
+    if let Some(jobs) = program_args.jobs {
+        rayon::ThreadPoolBuilder::new().num_threads(jobs).build_global()
+            .map_err(|e| anyhow!(format!("Unable to start {} worker threads: {}", jobs, e).red()))?;
     }
-    projects_map.values_mut().for_each(|f| f.images.sort_by(|a, b| a.name.cmp(&b.name)));
-    let mut sorted_project_names: Vec<String> = projects_map.keys().into_iter().map(|k| k.clone()).collect();
-    sorted_project_names.sort();
-
-    let (mut sp_icons_class_names, sp_icons_css_string) = {
-        if app_config.command_line_args.is_basic {
-            (Vec::new(), String::new())
-        } else {
-            print!("Parsing sp-icons... ");
-            match parse_special_file(&mut app_config.sp_icons_file_spec, &projects_map)? {
-                Some((sp_icons_class_names, sp_icons_css_string)) => {
-                    println!("{}", "OK".green());
-                    (sp_icons_class_names, sp_icons_css_string)
-                },
-                None => {
-                    println!("{}", "No sp-icons file found".yellow());
-                    (Vec::new(), String::new())
-                }
-            }       
-        }
-    };
-    sp_icons_class_names.sort();
-    let (mut font_awesome_class_names, font_awesome_css_string) = {
-        if app_config.command_line_args.is_basic {
-            (Vec::new(), String::new())
-        } else {
-            print!("Parsing font-awesome... ");
-            match parse_special_file(&mut app_config.font_awesome_file_spec, &projects_map)? {
-                Some((font_awesome_class_names, font_awesome_css_string)) => {
-                    println!("{}", "OK".green());
-                    (font_awesome_class_names, font_awesome_css_string)
-                },
-                None => {
-                    println!("{}", "No font-awesome file found".yellow());
-                    (Vec::new(), String::new())
-                }
-            }
-        }
-    };
-    font_awesome_class_names.sort();
 
-    let html = generate_html_page_as_string(&projects_map, &sorted_project_names, &sp_icons_class_names, &sp_icons_css_string,
-        &font_awesome_class_names, &font_awesome_css_string, &app_config)?;
-    write_to_file(html, &app_config)?;
-    println!("\nGenerated html file: {}", app_config.output_file_path);
+    statusln!("img-dumper {}\n", VERSION_ID);
 
-    open_generated_file_in_the_browser(&app_config);
+    match program_args.command {
+        SubCommand::Scan => run_scan(program_args)?,
+        SubCommand::Dupes => run_dupes(program_args)?,
+        SubCommand::Unused => run_unused(program_args)?,
+        SubCommand::Broken => run_broken(program_args)?,
+        SubCommand::Serve => run_serve(program_args)?,
+        SubCommand::Diff => run_diff(program_args)?,
+    }
 
-    println!("\nExec time: {:.2} secs", instant.elapsed().as_secs_f32());
+    statusln!("\nExec time: {:.2} secs", instant.elapsed().as_secs_f32());
 
     Ok(())
 }
 
-fn traverse_root_dir_and_make_project_map(app_config: &AppConfig) -> HashMap<String, ProjectDir> {
-    let mut project_dirs = HashMap::new();
-
-    let mut images = vec![];
-    let mut project_dir = &mut ProjectDir::default();
-
-    for entry in WalkDir::new(&app_config.root_dir)
-            .follow_links(true)
-            .into_iter()
-            .filter_entry(|e| {
-                e.file_name()
-                .to_str()
-                .map(|s| 
-                    !s.starts_with(".") &&
-                    !app_config.irrelevant_dir_names.contains(&s) &&
-                    (e.depth() != 1 || e.file_type().is_dir()))
-                .unwrap_or(false)
-    }) {
-        let entry = if let Ok(x) = entry { x } else { continue };
-        let entry_path = entry.path();
-        let entry_name = entry_path
-            .file_name()
-            .and_then(|os_str| os_str.to_str())
-            .unwrap_or("");
+fn run_scan(program_args: CommandLineArgs) -> anyhow::Result<()> {
+    let app_config = AppConfig::init(program_args)?;
+    let (scan_result, watched_projects) = scan_and_print_progress(&app_config, app_config.command_line_args.is_watching)?;
 
-        // if it is top-level project folder
-        if entry.depth() == 1 {
-            project_dir.images = images;
-
-            project_dir = project_dirs
-                .entry(entry_name.to_owned())
-                .or_insert_with(|| ProjectDir {
-                    name: entry_name.to_string(),
-                    path: entry_path.to_string_lossy().replace("\\","/"),
-                    images: Vec::new(),
-                });
-
-            images = vec![];
+    let renderer = get_renderer(&app_config);
+    if app_config.bundle_dir.is_some() {
+        write_report(&scan_result, renderer.as_ref(), &app_config)?;
+    } else {
+        let contents = renderer.render(&scan_result)?;
+        if app_config.command_line_args.is_portable {
+            warn_if_too_big(&contents);
         }
+        write_to_file(contents, &app_config)?;
+    }
+    if app_config.is_output_stdout() {
+        return Ok(());
+    }
+    match &app_config.bundle_dir {
+        Some(bundle_dir) => statusln!("\nGenerated the bundle: {}", bundle_dir),
+        None => statusln!("\nGenerated {} file: {}", app_config.command_line_args.format.get_name(), app_config.output_file_path),
+    }
 
-        let extension = entry_path.extension().unwrap_or_default().to_str().unwrap_or_default().to_lowercase();
-
-        if app_config.relevant_extensions.contains(&extension.as_str()) {
-            let name = entry_path.file_stem().unwrap_or_default().to_str().unwrap_or_default();
-            let img = Img {
-                name: name.to_owned(),
-                path: entry_path.to_string_lossy().replace("\\","/"),
-                extension: extension.to_owned(),
-            };
-            images.push(img);
-        }
+    if app_config.command_line_args.format == OutputFormat::Html {
+        open_in_the_browser(&app_config.output_file_path);
     }
 
-    project_dir.images = images;
+    if let Some(watched_projects) = watched_projects {
+        watcher::watch(&app_config, watched_projects, |scan_result| write_report(&scan_result, renderer.as_ref(), &app_config))?;
+    }
 
-    project_dirs
+    Ok(())
 }
 
-fn get_javascript_string(app_config: &AppConfig) -> String {
-    let mut js = 
-    "<script>
-    const inputElement = document.getElementById('search-input');
-    inputElement.addEventListener('input', handleSearchChange);
-
-    function handleSearchChange() {
-        let input, filter, uls, lis, a, i, span, txtValue;
-        input = document.getElementById('search-input');
-        filter = input.value.toUpperCase();
-        let relevant_lis_count = 0;
-        uls = document.getElementsByTagName('ul');
-        for (ul of uls) {
-            parent_project_area_div = ul.parentElement;
-            lis = ul.getElementsByTagName('li');
-            for (i = 0; i < lis.length; i++) {
-                extensionSpan = lis[i].getElementsByTagName('span')[0];
-                extensionValue = extensionSpan.textContent || extensionSpan.innerText;
-                nameSpan = lis[i].getElementsByTagName('span')[1];
-                nameValue = nameSpan.textContent || nameSpan.innerText;
-                if (nameValue.toUpperCase().indexOf(filter) > -1 && currentlySelectedExtensions.includes(extensionValue.toLowerCase())) {
-                    lis[i].style.display = '';
-                    relevant_lis_count += 1;
-                } else {
-                    lis[i].style.display = 'none';
-                }
-            }
-            if (relevant_lis_count == 0) {
-                parent_project_area_div.style.display = 'none';
-            } else {
-                parent_project_area_div.style.display = 'block';
-            }
-            relevant_lis_count = 0;
-        }
+// Writes the report to its file, or the whole bundle when there is one
+fn write_report(scan_result: &ScanResult, renderer: &dyn Renderer, app_config: &AppConfig) -> anyhow::Result<()> {
+    match &app_config.bundle_dir {
+        Some(bundle_dir) => write_bundle(scan_result, bundle_dir).map(|_| ()),
+        None => write_to_file(renderer.render(scan_result)?, app_config),
     }
+}
 
-    // handler to copy paths from titles of <li> elements
-    document.addEventListener('click', handleLiClick);
-    function handleLiClick($event) {
-        let target = $event.target;
-        if (target.parentElement.tagName.toLowerCase() === 'li') {
-            target = target.parentElement;
-        }
-        if (target.tagName.toLowerCase() === 'li') {
-            let titleValue = target.getAttribute('title');
-            if (!titleValue) {
-                return;
-            }
-            titleValue = titleValue.substring(0, titleValue.lastIndexOf('/'));
-            navigator.clipboard.writeText(titleValue)
-                .then(() => {
-                    console.log('Text copied to clipboard: ' + titleValue);
-                    const copyNotification = document.getElementById('copy-notification');
-                    copyNotification.classList.add('show');
-                    setTimeout(() => {
-                        copyNotification.classList.remove('show');
-                    }, 1000);
-                })
-                .catch((error) => {
-                    console.error('Error copying text to clipboard:', error);
-                });
-        }
+fn get_renderer(app_config: &AppConfig) -> Box<dyn Renderer> {
+    if app_config.command_line_args.is_portable {
+        Box::new(HtmlRenderer::portable())
+    } else {
+        app_config.command_line_args.format.get_renderer()
     }
+}
 
-    function toggleProjectArea($event) {
-        let element = $event.currentTarget;
-        let downChild = element.querySelector('span.down');
-        let upChild = element.querySelector('span.up');
-        let ul = element.parentElement.parentElement.querySelector('ul.images-area');
-        console.log('ul: ', ul);
-
-        // down arrow is showing in the beginning
-        if (getComputedStyle(downChild).display !== 'none') {
-            downChild.style.display = 'none';
-            ul.style.display = 'none';
-        } else {
-            downChild.style.display = '';
-            ul.style.display = '';
-        }
-
-        if (getComputedStyle(upChild).display !== 'none') {
-            upChild.style.display = 'none';
-        } else {
-            upChild.style.display = '';
-        }
-    } 
-
-    function handleCheckboxChange(event) {
-        const checkbox = event.currentTarget;
-        const checkboxValue = checkbox.value;
-        const isChecked = checkbox.checked;
-      
-        if (isChecked) {
-          currentlySelectedExtensions.push(checkboxValue);
-        } else {
-          currentlySelectedExtensions = currentlySelectedExtensions.filter(
-            (ext) => ext !== checkboxValue
-          );
-        }
-      
-        let relevant_lis_count = 0;
-        const uls = document.getElementsByTagName('ul');
+fn warn_if_too_big(contents: &str) {
+    if contents.len() as u64 > PORTABLE_SIZE_WARNING_BYTES {
+        statusln!("{}", format!("The report is {}, it may be too big to be sent by email or slow to open. \
+            Excluding directories or extensions makes it smaller.", format_bytes(contents.len() as u64)).yellow());
+    }
+}
 
-        inputFilter = document.getElementById('search-input').value.toUpperCase();
+fn run_serve(program_args: CommandLineArgs) -> anyhow::Result<()> {
+    let app_config = AppConfig::init(program_args)?;
+    let (scan_result, watched_projects) = scan_and_print_progress(&app_config, true)?;
+    server::serve(&app_config, scan_result, watched_projects.unwrap_or_default())
+}
 
-        for (const ul of uls) {
-          const parent_project_area_div = ul.parentElement;
-          const lis = ul.getElementsByTagName('li');
-      
-          for (const li of lis) {
-            extensionSpan = li.getElementsByTagName('span')[0];
-            extensionValue = extensionSpan.textContent || extensionSpan.innerText;
-            nameSpan = li.getElementsByTagName('span')[1];
-            nameValue = nameSpan.textContent || nameSpan.innerText;
+// Scans with the progress printed on the terminal. When `is_watching`, the traversal of every project is returned too,
+// so that the watcher can update only the ones that change.
+fn scan_and_print_progress(app_config: &AppConfig, is_watching: bool) -> anyhow::Result<(ScanResult, Option<BTreeMap<String, ProjectDir>>)> {
+    if let Some(config_file_path) = &app_config.config_file_path {
+        statusln!("Config file: {}", config_file_path);
+    }
+    let scanner = &app_config.scanner;
+    statusln!("Root folder: {}\n", scanner.get_root_dir());
+
+    status!("Parsing projects... ");
+    let traversed_projects = scanner.traverse_projects();
+    save_scan_cache(scanner);
+    let watched_projects = is_watching.then(|| traversed_projects.clone());
+    let projects_map = scanner.resolve_projects(traversed_projects);
+    if projects_map.is_empty() {
+        statusln!("{}", "No icons could be found for any projects".yellow());
+    } else {
+        statusln!("{} ({} found)", "OK".green(), projects_map.len());
+    }
 
-            if (currentlySelectedExtensions.includes(extensionValue.toLowerCase()) && (!nameValue || nameValue.toUpperCase().indexOf(inputFilter) > -1)) {
-              if (window.getComputedStyle(li).display === 'none') {
-                li.style.display = '';
-              }
-              relevant_lis_count += 1;
-            } else {
-                if (window.getComputedStyle(li).display !== 'none') {
-                    li.style.display = 'none';
+    let mut icon_sets = Vec::new();
+    for file_spec in scanner.get_special_file_specs() {
+        status!("Parsing {}... ", file_spec.title);
+        match scanner.parse_special_file(file_spec, &projects_map)? {
+            Some(icon_set) => {
+                statusln!("{}", "OK".green());
+                for path in &icon_set.missing_files {
+                    statusln!("{}", format!("  `{}` is referenced by the file but doesn't exist", path).yellow());
                 }
+                icon_sets.push(icon_set);
+            },
+            None => {
+                statusln!("{}", format!("No {} file found", file_spec.title).yellow());
             }
-          }
-      
-          if (relevant_lis_count === 0) {
-            parent_project_area_div.style.display = 'none';
-          } else {
-            parent_project_area_div.style.display = 'block';
-          }
-      
-          relevant_lis_count = 0;
         }
-      }
-    ".to_owned();
-
-    let joined_values = app_config
-    .relevant_extensions
-    .iter()
-    .map(|value| format!("\"{}\"", value))
-    .collect::<Vec<String>>()
-    .join(",");
-
-    js.push_str(&format!("
-    let currentlySelectedExtensions = [{}];
-    ",joined_values));
-
-    js.push_str("</script>");
-
-    js
-}
-
-fn generate_html_page_as_string(
-        project_dirs: &HashMap<String, ProjectDir>,
-        sorted_project_names: &Vec<String>,
-        sp_icons_class_names: &Vec<String>,
-        sp_icons_css_string: &String,
-        font_awesome_class_names: &Vec<String>,
-        font_awesome_css_string: &String,
-        app_config: &AppConfig) -> anyhow::Result<String> {
-    let mut html = String::from("<html lang='en'> <head> <title>Spectre icons</title> </head> <body> <div id='page-container'>");
-    
-    html += 
-    "<div class='search-container'>
-        <div>
-            <label for='search-input'>Search:</label>
-            <input id='search-input' name='search-input'>
-        </div>
-        <div class='flex-center flex-1'>";
-        for extension in &app_config.relevant_extensions {
-            html += &format!(
-            "<div class='checkbox-item'>
-                <input type='checkbox' id='checkbox-{}' name='checkbox-{}' value='{}' onchange='handleCheckboxChange(event)' checked>
-                <label for='checkbox-{}'>{}</label>
-            </div>", extension, extension, extension, extension, extension);
-        }
-    html += &format!(
-    "   </div>
-            <div class='date-marker-area'>
-                <span>{}</span>
-                <span>Generated at:</span>
-                <span>{}</span>
-            </div>
-    </div>", VERSION_ID, app_config.exec_date_time.format("%d/%m/%Y - %H:%M:%S").to_string());
-
-    html +=
-    "<div id='copy-notification' class='fade'>
-        <span> Copied path to clipboard!</span>
-    </div>";
-
-    if !sp_icons_class_names.is_empty() {
-        if app_config.sp_icons_file_spec.selected_abs_dir.is_none() {
-            return Err(anyhow!("sp-icons file path should be set but it isn't (this is a bug)".red()));
-        }
-        let sp_icons_html_string = generate_html_string_from_classes(&app_config.sp_icons_file_spec, "sp-icons", "sp-icons-", "svg", &sp_icons_class_names);
-        html += &sp_icons_html_string;
     }
 
-    if !font_awesome_class_names.is_empty() {
-        if app_config.font_awesome_file_spec.selected_abs_dir.is_none() {
-            return Err(anyhow!("font-awesome file path should be set but it isn't (this is a bug)".red()));
-        }
-        let font_awesome_html_string = generate_html_string_from_classes(&app_config.font_awesome_file_spec, "fa", "fa-", "svg", &font_awesome_class_names);
-        html += &font_awesome_html_string;
+    let mut scan_result = scanner.make_result(projects_map, icon_sets);
+    if let Some(previous_snapshot) = &app_config.previous_snapshot {
+        mark_new_images(&mut scan_result, previous_snapshot);
+        statusln!("New images since the previous snapshot: {}", count_new_images(&scan_result));
     }
-
-    for project_name in sorted_project_names {
-        let curr_project_dir = project_dirs.get(project_name).unwrap();
-        html += &format!("<div class='project-area'>
-                            <div class='flex-center'>
-                                <div class='name-arrow-container' onclick='toggleProjectArea(event)'>
-                                    <span class='down arrow-utf-8'>&#9660</span>
-                                    <span class='up arrow-utf-8' style='display: none'>&#9650</span>
-                                    <h1 class='title margin-right-05'>{}</h1>
-                                </div>
-                                <span>({})</span>
-                            </div>
-                            <ul class='images-area'>", project_name, curr_project_dir.path);
-
-        for (i, image) in curr_project_dir.images.iter().enumerate() {
-        html += &format!("<li class='image-container' title='{}'> <div class='extension-stamp color-{}'> <span>{}</span> </div> <img src=\"{}\" alt=\"{}\" /> <span>{}</span> </li>
-                    ", image.path, image.extension, image.extension, image.path, i, image.name);
-        }
-
-        html += "</ul></div>";
+    if let Some(history_dir) = &app_config.history_dir {
+        statusln!("Saved the snapshot: {}", save_snapshot(history_dir, &scan_result)?);
     }
-
-    html += "
-    <footer class='author-area'>
-        <span>Petros Papatheodorou 2023</span>
-    </footer>";
-    
-    html += "</div></body>";
-    html += &get_css_string(&sp_icons_css_string, &font_awesome_css_string);
-    html += &get_javascript_string(app_config);
-    html += "</html>";
-
-    Ok(html)
-}
-
-fn generate_html_string_from_classes(file_spec: &ParsableFileSpec, extra_class: &str, class_prefix: &str, extension: &str, classes: &Vec<String>) -> String {
-    let mut html = String::with_capacity(1000);
-    html += &format!("<div class='project-area'>
-                        <div class='flex-center'>
-                            <div class='name-arrow-container' onclick='toggleProjectArea(event)'>
-                                <span class='down arrow-utf-8'>&#9660</span>
-                                <span class='up arrow-utf-8' style='display: none'>&#9650</span>
-                                <h1 class='title margin-right-05'>{}</h1>
-                            </div>
-                            <span>({}) ---- class names are normally prefixed with `{}`</span>
-                        </div>", file_spec.title, file_spec.selected_abs_dir.as_ref().unwrap(), class_prefix);
-
-    html += "<ul class='images-area'>\n";
-    for class in classes {
-        html += &format!("<li class='image-container'> <div class='extension-stamp color-{}'> <span>{}</span> </div> <i class='{} {}'></i> <span>{}</span> </li>
-            ", extension, extension, extra_class, class, class.strip_prefix(class_prefix).unwrap_or(class));
+    let broken_count: usize = scan_result.projects.values().map(|project_dir| project_dir.broken_references.len()).sum();
+    if broken_count > 0 {
+        statusln!("{}", format!("{} broken image references, see `img-dumper broken`", broken_count).yellow());
+    }
+    if scanner.get_similarity_threshold().is_some() {
+        statusln!("Similar images: {} groups", scan_result.similar_clusters.len());
     }
-    html.push_str("</ul></div>\n");
 
-    html
+    Ok((scan_result, watched_projects))
 }
 
-fn get_css_string(sp_icons_css_string: &String, font_awesome_css_string: &String) -> String {
-    let mut css = String::from("<style>
-        body {
-            background-color: #f1f1f1;
-            font-family: Arial, Helvetica, sans-serif;
-        }
-
-        #page-container {
-            position: relative;
-            min-height: 100vh;
-        }
-
-        ul {
-            padding: 0;
-            margin: 0;
-        }
-
-        .search-container {
-            position: sticky;
-            top: 0;
-            z-index: 1;
-            display: flex;
-            align-items: center;
-            column-gap: 3em;
-            box-shadow: 0 2px 4px rgba(0, 0, 0, 0.4);
-            background-color: #d3d3d3;
-            padding: 6px;
-        }
-
-        .search-container input {
-            font-size: 1em;
-            height: 1.6em;
-            padding: 0 0.3em;
-            border-radius: 3px;
-            border: none;
-            box-shadow: 0 0 3px rgba(0, 0, 0, 0.4);
-        }
-
-        .checkbox-item {
-            display: flex;
-            width: fit-content; 
-            width: -moz-fit-content;
-            align-items: center; 
-            column-gap: 0.2em;
-            cursor: pointer;
-        }
-
-        .checkbox-item ~ .checkbox-item {
-            margin-left: 0.8em;
-        }
-
-        .checkbox-item input {
-            width: 1em;
-            cursor: pointer;
-        }
-
-        .checkbox-item label {
-            cursor: pointer;
-        }
-
-        .date-marker-area span:first-child {
-            color: #3a3a3a;
-            font-size: 0.85em;
-            font-style: italic;
-        }
+fn run_dupes(program_args: CommandLineArgs) -> anyhow::Result<()> {
+    let app_config = AppConfig::init(program_args)?;
+    let scanner = &app_config.scanner;
+    statusln!("Root folder: {}\n", scanner.get_root_dir());
 
-        .date-marker-area span:last-child {
-            border: #fafafa; 
-            border-style: inset; 
-            padding: 0.1em; 
-        }
+    status!("Parsing projects... ");
+    let projects_map = scanner.scan_projects();
+    statusln!("{} ({} found)", "OK".green(), projects_map.len());
+    save_scan_cache(scanner);
 
-        #copy-notification {
-            position: fixed; 
-            z-index: 1; 
-            right: 10; 
-            bottom: 15;
-            display: flex; 
-            align-items: center;
-            width: fit-content; 
-            width: -moz-fit-content;
-            background-color: rgba(0,0,0,0.8); 
-            border-radius: 16px; 
-        }
-
-        #copy-notification span:first-child {
-            color: white; 
-            padding: 0.5em 1em; 
-            font-style: italic;
-        }
-
-        .fade {
-            opacity: 0;
-            transition: opacity 0.3s ease-in-out;
-        }
-        
-        .fade.show {
-            opacity: 1;
-        }
-
-        .name-arrow-container {
-            cursor: pointer;
-            display: flex;
-            align-items: center;
-        }
-
-        .arrow-utf-8 {
-            font-size: 0.8em;
-            margin-top: 0.3em;
-            padding: 0.5em;
-        }
-
-        .arrow-utf-8.up {
-            font-size: 1em;
-            padding: 0.4em;
-        }
-
-        span.up.arrow-utf-8 {
-            color: #1fbc1f;
-        }
-
-        .sp-icons {
-            font-size: 2.5em;
-            margin-left: auto;
-            margin-right: auto;
-        }
-
-        .fa {
-            font-size: 2.5em !important;
-            margin-left: auto;
-            margin-right: auto;
-        }
-
-        .flex-center {
-            display: flex;
-            align-items: center;
-        }
-
-        .flex-1 {
-            flex: 1;
-        }
-
-        .margin-right-05 {
-            margin-right: 0.5em;
-        }
-
-        .project-area {
-            margin-bottom: 20px;
-        }
-
-        .title {
-            margin-top: 0.3em;
-            margin-bottom: 0.3em;
-        }
-
-        .title+span {
-            font-size: 0.9em;
-            color: #333333;
-        }
-
-        .images-area {
-            display: flex;
-            flex-wrap: wrap;
-            align-items: center;
-            column-gap: 10px;
-            row-gap: 6px;
-        }
-
-        .image-container  {
-            position: relative;
-            display: flex;
-            flex-direction: column;
-            word-wrap: break-word;
-            width: 4.5em;
-        }
-
-        .image-container[title] {
-            cursor: copy; 
-        }
-
-        .extension-stamp {
-            display: none;
-            width: 1.8em;
-            height: 1em;
-            border-radius: 50%;
-            z-index: 1;
-            top: -10;
-            left: -6;
-            position: absolute;
-        }
+    let duplicates = scanner.make_result(projects_map, Vec::new()).duplicates;
+    if duplicates.is_empty() {
+        statusln!("\n{}", "No identical images could be found".green());
+        return Ok(());
+    }
 
-        .extension-stamp > span {
-            color: black !important;
+    statusln!("\n{} groups of identical files, {} wasted", duplicates.len(),
+        format_bytes(get_total_wasted_bytes(&duplicates)).yellow());
+    for group in &duplicates {
+        statusln!("\n{} copies, {} each, {} wasted", group.locations.len(), format_bytes(group.size_bytes),
+            format_bytes(group.get_wasted_bytes()).yellow());
+        let name_width = group.locations.iter().map(|location| location.project_name.len()).max().unwrap_or(0);
+        for location in &group.locations {
+            statusln!("    {:<width$}  {}", location.project_name, location.path, width = name_width);
         }
+    }
 
-        .image-container:hover > .extension-stamp {display: block; }
+    Ok(())
+}
 
-        .color-svg {
-            background-color: #e971e9;
-        }
+fn run_unused(program_args: CommandLineArgs) -> anyhow::Result<()> {
+    let app_config = AppConfig::init(program_args)?;
+    let scanner = &app_config.scanner;
+    statusln!("Root folder: {}\n", scanner.get_root_dir());
 
-        .color-png {
-            background-color: #71e98d;
-        }
+    status!("Parsing projects and their source files... ");
+    let projects_map = scanner.scan_projects();
+    statusln!("{} ({} found)", "OK".green(), projects_map.len());
+    save_scan_cache(scanner);
 
-        .color-jpg, .color-jpeg {
-            background-color: #ff9ea5;
-        }
+    let unused_images = get_unused_images(&projects_map);
+    if unused_images.is_empty() {
+        statusln!("\n{}", "Every image is referenced by at least one source file".green());
+        return Ok(());
+    }
 
-        .color-gif {
-            background-color: #afefff;
-        }
-        
-        .color-ico {
-            background-color: #ffef00;
-        }
-        
-        .color-bmp {
-            background-color: #ff5656;
+    let reclaimable_bytes: u64 = unused_images.iter().map(|(_, img)| img.size_bytes).sum();
+    statusln!("\n{} unused images, {} reclaimable", unused_images.len(), format_bytes(reclaimable_bytes).yellow());
+    let mut current_project_name = "";
+    for (project_dir, img) in &unused_images {
+        if project_dir.name != current_project_name {
+            current_project_name = &project_dir.name;
+            statusln!("\n{}", project_dir.name);
         }
+        statusln!("    {:>9}  {}", format_bytes(img.size_bytes), img.path);
+    }
 
-        .image-container span {
-            display: block;
-            font-size: 0.8em;
-            text-align: center;
-            color: #818181;
-        }
+    Ok(())
+}
 
-        .image-container img {
-            width: 3em;
-            height: auto;
-            max-height: 3.5em;
-            margin-left: auto;
-            margin-right: auto;
-        }
-        
-        footer.author-area {
-            position: absolute;
-            bottom: 0;
-            right: 0;
-            display: flex;
-            justify-content: right;
-        }
+fn run_broken(program_args: CommandLineArgs) -> anyhow::Result<()> {
+    let app_config = AppConfig::init(program_args)?;
+    let scanner = &app_config.scanner;
+    statusln!("Root folder: {}\n", scanner.get_root_dir());
 
-        footer.author-area span {
-            color: #818181;
-            font-size: 0.9em;
-            font-style: italic;
-            margin-right: 0.5em;
-        }
+    status!("Parsing projects and their source files... ");
+    let projects_map = scanner.scan_projects();
+    statusln!("{} ({} found)", "OK".green(), projects_map.len());
+    save_scan_cache(scanner);
 
-        \n\n");
-    if !sp_icons_css_string.is_empty() {
-        css += "/*===================>  SP ICONS AREA <===================*/\n\n";
-        css += &sp_icons_css_string;
+    let broken_count: usize = projects_map.values().map(|project_dir| project_dir.broken_references.len()).sum();
+    if broken_count == 0 {
+        statusln!("\n{}", "Every image reference points to an existing file".green());
+        return Ok(());
     }
-    if !font_awesome_css_string.is_empty() {
-        css += "/*===================>  FONT AWESOME AREA <===================*/\n\n";
-        css += &font_awesome_css_string;
-    }
-    css += "</style>";
-
-    css
-}
 
-fn parse_special_file( file_spec: &mut ParsableFileSpec, projects_map: &HashMap<String, ProjectDir>)
--> anyhow::Result<Option<(Vec<String>, String)>> {
-    let mut found_file_path = file_spec.known_abs_path.to_owned();
-    let mut found_file_dir = file_spec.known_abs_dir.to_owned();
-    let mut has_found_valid_path = true;
-    if !PathBuf::from(&file_spec.known_abs_path).exists() {
-        has_found_valid_path = false;
-        if let Some(relative_dir) = &file_spec.relative_dir {
-            for project in projects_map.values() {
-                let file_dir_str = format!("{}{}", project.path, relative_dir);
-                let file_path_str = format!("{}/{}.{}", file_dir_str, file_spec.name, file_spec.extension);
-                if PathBuf::from(&file_path_str).exists() {
-                    found_file_dir = file_dir_str;
-                    found_file_path = file_path_str;
-                    has_found_valid_path = true;
-                    break;
-                }
+    statusln!("\n{} broken references", broken_count.to_string().yellow());
+    for project_dir in projects_map.values().filter(|project_dir| !project_dir.broken_references.is_empty()) {
+        statusln!("\n{}", project_dir.name);
+        for broken_reference in &project_dir.broken_references {
+            let reference = &broken_reference.reference;
+            let kind = match &broken_reference.kind {
+                BrokenReferenceKind::Missing => broken_reference.kind.get_name().red(),
+                BrokenReferenceKind::CaseMismatch { .. } => broken_reference.kind.get_name().yellow(),
+            };
+            statusln!("    {}:{}  {}  {}", reference.source_path, reference.line, kind, reference.target);
+            if let BrokenReferenceKind::CaseMismatch { actual_path } = &broken_reference.kind {
+                statusln!("        the file is {}", actual_path);
             }
         }
     }
 
-    if !has_found_valid_path {
-        return Ok(None);
-    } else {
-        file_spec.selected_abs_dir = Some(found_file_dir.clone());
-    }
-
-    let reader = BufReader::new(File::open(&found_file_path).context(
-        format!("specified file path `{}` for {} is not valid", found_file_path, &file_spec.title).red())?);
-
-    let a = (file_spec.parser_fn)(file_spec, reader)?;
-
-    Ok(Some(a))
+    Ok(())
 }
 
-fn parse_css_file(file_spec: &ParsableFileSpec, reader: BufReader<File>) -> anyhow::Result<(Vec<String>, String)> {
-    let mut content = String::with_capacity(file_spec.approximate_size_bytes);
-    let mut class_names = Vec::with_capacity(150);
-    for line in reader.lines() {
-        let mut start_index = 0;
-        let mut line = line.context(format!("Failed to read a line, while parsing {}", file_spec.title).red())?;
-        while let Some(index) = line[start_index..].find("url('") {
-            let relative_path_start = start_index + index + 5;
-            if let Some(index_end) = &line[relative_path_start..].find("')") {
-                let relative_path_end = relative_path_start + index_end;
-                let absolute_path = join_paths(&file_spec.selected_abs_dir.as_ref().unwrap(), &line[relative_path_start..relative_path_end], "/");
-                line.replace_range(relative_path_start..relative_path_end, &absolute_path);
-                start_index = relative_path_end + 2;
-            } else {
-                break;
-            }
-        }
-        
-        if let Some(class_name) = line.strip_suffix(":before {") {
-            class_names.push(class_name.trim()[1..].to_owned());
+fn run_diff(program_args: CommandLineArgs) -> anyhow::Result<()> {
+    let app_config = AppConfig::init(program_args)?;
+    let diff_paths = &app_config.command_line_args.diff_paths;
+    let old = Snapshot::load(&diff_paths[0])?;
+    let new = match diff_paths.get(1) {
+        Some(path) => Snapshot::load(path)?,
+        None => {
+            let (scan_result, _) = scan_and_print_progress(&app_config, false)?;
+            statusln!();
+            Snapshot::from_scan_result(&scan_result, app_config.scanner.get_root_dir())
         }
+    };
+    statusln!("Comparing {} ({})
+     with {} ({})", old.label, old.generated_at, new.label, new.generated_at);
 
-        content.push_str(&line);
-        content.push('\n');
+    let diff = diff_snapshots(old, new);
+    if diff.is_empty() {
+        statusln!("\n{}", "Nothing changed".green());
+    } else {
+        statusln!("\n{} added, {} removed, {} renamed, {} modified", diff.count_changes("added"), diff.count_changes("removed"),
+            diff.count_changes("renamed"), diff.count_changes("modified"));
     }
-
-    Ok((class_names, content))
-}
-
-fn get_htdocs_path() -> Option<String> {
-    let os = env::consts::OS;
-
-    match os {
-        "windows" => {
-            let default_path_str = "C:/xampp/htdocs";
-            let xampp_htdocs_path = PathBuf::from(default_path_str);
-            if xampp_htdocs_path.exists() {
-                return Some(default_path_str.to_owned());
-            }
+    for project_diff in &diff.projects {
+        statusln!("\n{}", project_diff.name);
+        for change in &project_diff.changes {
+            let (kind, description) = match change {
+                ImageChange::Added(img) => (change.get_name().green(), format!("{}  {}", img.relative_path, format_bytes(img.size_bytes))),
+                ImageChange::Removed(img) => (change.get_name().red(), img.relative_path.clone()),
+                ImageChange::Renamed { old, new } => (change.get_name().cyan(), format!("{} -> {}", old.relative_path, new.relative_path)),
+                ImageChange::Modified { old, new } => (change.get_name().yellow(),
+                    format!("{}  {} -> {}", new.relative_path, format_bytes(old.size_bytes), format_bytes(new.size_bytes))),
+            };
+            statusln!("    {:<8}  {}", kind, description);
         }
-        "macos" => {
-            let default_path_str = "/Applications/XAMPP/xamppfiles/htdocs";
-            let xampp_htdocs_path = PathBuf::from(default_path_str);
-            if xampp_htdocs_path.exists() {
-                return Some(default_path_str.to_owned());
-            }
+    }
+    for icon_set_diff in &diff.icon_sets {
+        statusln!("\n{} classes", icon_set_diff.id);
+        for class_name in &icon_set_diff.added_class_names {
+            statusln!("    {}", format!("+ {}", class_name).green());
         }
-        "linux" => {
-            for path_str in ["/opt/lampp/htdocs", "/var/www/html"] {
-                let xampp_htdocs_path = PathBuf::from(path_str);
-                if xampp_htdocs_path.exists() {
-                    return Some(path_str.to_owned());
-                }
-            }
+        for class_name in &icon_set_diff.removed_class_names {
+            statusln!("    {}", format!("- {}", class_name).red());
         }
-        _ => {return None}
     }
 
-    None
-}
-
-fn parse_args() -> anyhow::Result<Option<CommandLineArgs>> {
-    let line = env::args().skip(1).collect::<Vec<String>>().join(" ");
-    let mut commands = line.split("--");
-
-    if line.trim().starts_with("--") {
-        //ignoring the empty first element that is caused by splitting
-        commands.next();
+    write_to_file(generate_diff_html_page_as_string(&diff), &app_config)?;
+    if app_config.is_output_stdout() {
+        return Ok(());
     }
+    statusln!("\nGenerated html diff: {}", app_config.output_file_path);
+    open_in_the_browser(&app_config.output_file_path);
 
-    let (mut dir, mut target, mut name, mut is_basic) = (None, None, None, false);
-    for command in commands {
-        let (command_name, arguments) = match command.find(" ") {
-            Some(index) => command.split_at(index),
-            None => (command.trim(), "")
-        };
-        if command_name == Argument::Dir.get_name() {
-            let mut path = arguments.trim().replace("\\", "/");
-            path = path.strip_prefix('"').unwrap_or(&path).strip_suffix('"').unwrap_or(&path).to_owned();
-            if path.is_empty() {
-                println!("{}", Argument::Dir.get_help_msg());
-                return Err(anyhow!("No argument provided for --dir".red()));
-            }
-            dir = Some(path);
-        } else if command_name == Argument::Target.get_name() {
-            let path = arguments.trim();
-            if path.is_empty() {
-                println!("{}", Argument::Target.get_help_msg());
-                return Err(anyhow!("No argument provided for --target".red()));
-            }
-            target = Some(path.to_owned());
-        } else if command_name == Argument::Name.get_name() {
-            let _name = arguments.trim();
-            if _name.is_empty() {
-                println!("{}", Argument::Name.get_help_msg());
-                return Err(anyhow!("No argument provided for --name".red()));
-            }
-            name = Some(_name.to_owned());
-        } else if command_name == Argument::Basic.get_name() {
-            let flag = arguments.trim();
-            if !flag.is_empty() {
-                println!("Warning: {}\n", format!("Ignoring argument for --{}",Argument::Basic.get_name()).yellow());
-            }
-            is_basic = true;
-        } else if command_name == Argument::Help.get_name() {
-            return Ok(None);
-        } else if !command_name.trim().is_empty() {
-            return Err(anyhow!(format!("Unknown command: {}", command_name).red()));
-        }
-    }
-
-    let program_args = CommandLineArgs { dir, target, name, is_basic };
-
-    Ok(Some(program_args))
-}
-
-pub fn get_trimmed_if_not_empty(str: &str) -> Option<String> {
-    let str = str.trim();
-    if str.is_empty() {None}
-    else {Some(str.to_owned())}
-}
-
-fn join_paths(base_absolute_path: &str, relative_path: &str, connective_str: &str) -> String {
-    let concated = format!("{}{}{}", base_absolute_path, connective_str, relative_path);
-    concated.replace("\\", "/").to_owned()
+    Ok(())
 }
 
-fn convert_to_absolute(s: &str) -> String {
-    let p = Path::new(s);
-    if p.is_absolute() {
-        return s.replace("\\", "/");
-    }
-
-    // The "canonicalize" function, (at least on windows) seems to put the weird prefix
-    // "\\?\" before the path and it also puts forward slashes that we want to convert for compatibility.  
-    if let Ok(buf) = std::fs::canonicalize(p) {
-        let str_path = buf.to_str().unwrap();
-        str_path.strip_prefix(r"\\?\").unwrap_or(str_path).replace("\\", "/")
-    } else {
-        s.replace("\\", "/")
+// Failing to write the cache only makes the next scan slower, so it is not fatal either
+fn save_scan_cache(scanner: &Scanner) {
+    if let Err(e) = scanner.save_cache() {
+        statusln!("{}", format!("Failed to save the scan cache: {}", e).yellow());
     }
 }
 
-fn open_generated_file_in_the_browser(app_config: &AppConfig) {
-    // Open the HTML file in the default browser
-    if cfg!(target_os = "windows") {
+// Opens the file or the url in the default browser. Failing to do so is not fatal, the path is printed anyway.
+// The opener is not waited for, since without a desktop environment `xdg-open` only exits when the browser does,
+// and watching or serving starts right after.
+fn open_in_the_browser(target: &str) {
+    let child = if cfg!(target_os = "windows") {
         // Windows command
         Command::new("cmd")
-            .args(&["/C", "start", "", &app_config.output_file_path])
+            .args(["/C", "start", "", target])
             .spawn()
-            .expect("Failed to open HTML file in the browser");
     } else if cfg!(target_os = "macos") {
         // macOS command
         Command::new("open")
-            .arg(&app_config.output_file_path)
+            .arg(target)
             .spawn()
-            .expect("Failed to open HTML file in the browser");
     } else {
         // Linux command
         Command::new("xdg-open")
-            .arg(&app_config.output_file_path)
+            .arg(target)
             .spawn()
-            .expect("Failed to open HTML file in the browser");
+    };
+    if child.is_err() {
+        statusln!("{}", "Failed to open it in the browser".yellow());
     }
 }
 
 fn write_to_file(contents: String, app_config: &AppConfig) -> anyhow::Result<()>{
-    let mut file = File::create(&app_config.output_file_path).map_err(|_| anyhow!("Failed to create the .html file".red()))?;
+    if app_config.is_output_stdout() {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(contents.as_bytes()).context("Failed to write to stdout".red())?;
+        stdout.flush()?;
+        return Ok(());
+    }
+    let mut file = File::create(&app_config.output_file_path)
+        .map_err(|_| anyhow!(format!("Failed to create the .{} file", app_config.command_line_args.format.get_extension()).red()))?;
     file.write_all(contents.as_bytes()).context("Failed to write to file".red())?;
     Ok(())
 }
 
-#[derive(Debug)]
-struct AppConfig <'a> {
-    pub command_line_args: CommandLineArgs,
-
-    // the date and time when the program was executed and the html file was generated
-    pub exec_date_time: DateTime<Local>,
-
-    // folder that contains the projects in which we want to search for Icons. By default it is the path to htdocs
-    pub root_dir : String, 
-
-    // full path of generated file
-    pub output_file_path : String,
-
-    // info about the font-awesome css file
-    pub font_awesome_file_spec: ParsableFileSpec<'a>,
-    
-    // info about the sp-icons css file
-    pub sp_icons_file_spec: ParsableFileSpec<'a>,
-
-    // names of folders that should be ignored in each project, like node_modules, bower_components, ...
-    pub irrelevant_dir_names: Vec<&'static str>,
-
-    // extensions that we want to search for, like svg, png, ...
-    pub relevant_extensions: Vec<&'static str>,
-}
-
-// #[derive(Debug)]
-struct ParsableFileSpec <'a> {
-    // a descriptive title for the file
-    pub title: &'a str,
-    // the file name without extension
-    pub name: &'a str,
-    pub extension: &'a str,
-    pub known_abs_dir: String,
-    pub known_abs_path: String,
-    // relative paths are relative to a *project* (top level folder inside root dir)
-    // if they are None, it means that the file can only be present in one place (known_abs_path)
-    pub relative_dir: Option<&'a str>,
-    pub relative_path: Option<String>,
-    // this optional get populated with the value only when the directory is validated that it exists
-    pub selected_abs_dir: Option<String>,
-    pub approximate_size_bytes: usize,
-    pub parser_fn: fn(&Self, BufReader<File>) -> anyhow::Result<(Vec<String>, String)>,
-}
-
-impl <'a>Debug for ParsableFileSpec<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("ParsableFileSpec").field("title", &self.title).field("name", &self.name).field("extension", &self.extension).field("known_abs_dir", &self.known_abs_dir).field("known_abs_path", &self.known_abs_path).field("relative_dir", &self.relative_dir).field("relative_path", &self.relative_path).field("selected_abs_dir", &self.selected_abs_dir).field("approximate_size_bytes", &self.approximate_size_bytes).finish()
-    }
-}
-
-impl <'a> ParsableFileSpec <'a>  {
-    fn new(title: &'a str, name: &'a str, extension: &'a str, known_abs_dir: String, relative_dir: Option<&'a str>, approximate_size_bytes: usize, 
-            parser_fn: fn(&Self, BufReader<File>) -> anyhow::Result<(Vec<String>, String)>) -> Self {
-        let known_abs_path = format!("{}/{}.{}", known_abs_dir, name, extension);
-        let relative_path = match &relative_dir {
-            Some(dir) => Some(format!("{}/{}.{}", dir, name, extension)),
-            None => None,
-        };
-        Self {
-            title,
-            name,
-            extension,
-            relative_dir,
-            relative_path,
-            known_abs_dir,
-            selected_abs_dir: None,
-            known_abs_path,
-            approximate_size_bytes,
-            parser_fn
-        }
-    }
-}
-
-#[derive(Debug, Default, Clone)]
-struct ProjectDir {
-    pub name: String,
-    pub path: String,
-    pub images: Vec<Img>,
-}
-
-#[derive(Debug, Clone)]
-struct Img {
-    pub name: String,
-    pub path: String,
-    pub extension: String,
-}
-
-#[derive(Debug, Default)]
-struct CommandLineArgs {
-    pub dir: Option<String>,
-    pub target: Option<String>,
-    pub name: Option<String>,
-    pub is_basic: bool,
-}
-
-impl <'a> AppConfig<'a> {
-    pub fn init(args: CommandLineArgs) -> anyhow::Result<Self> {
-        let root_dir = {
-            if let Some(dir) = &args.dir {
-                dir.clone()
-            } else {
-                if let Some(x) = get_htdocs_path() {
-                    x
-                } else {
-                    return Err(anyhow!("Unable to find htdocs folder and no custom root directory provided (--dir <path>)".red()));
-                }
-            }
-        };
-        let root_dir = convert_to_absolute(&root_dir);
-
-        if !Path::new(&root_dir).exists() {
-            return Err(anyhow!(format!("The (root) directory '{}' does not exist", root_dir).red()));
-        }
-        let mut output_file_name = 
-            if let Some(name) = &args.name {
-                name.to_owned()
-            } else {
-                "icons_report_generated".to_owned()
-            };
-        output_file_name.push_str(".html");
-
-        let output_file_path =
-            if let Some(target) = &args.target {
-                let path = PathBuf::from(target);
-                if !path.is_dir() {
-                    return Err(anyhow!(format!("The target '{}' is not a directory", target).red()));
-                }
-                path
-            } else {
-                let desktop_dir = dirs::desktop_dir();
-                if let Some(dir) = desktop_dir {
-                    dir
-                } else {
-                    Path::new(".").to_path_buf()
-                }
-            };
-        let output_file_path = convert_to_absolute(&join_paths(&output_file_path.to_string_lossy(), &output_file_name, "/"));
-
-        let font_awesome_file_spec = ParsableFileSpec::new("font-awesome", "font-awesome", "css",
-            root_dir.clone() + "/mega-commons-angular-js/bower_components/components-font-awesome/css",
-            Some("/bower_components/components-font-awesome/css"), 38000, parse_css_file);
-        let sp_icons_file_spec = ParsableFileSpec::new("sp-icons", "style", "css", 
-            root_dir.clone() + "/mega-commons-angular-js/assets/fonts/sp-icons",
-            None, 15000, parse_css_file);
-
-        Ok (Self { 
-            command_line_args: args,
-            exec_date_time: Local::now(),
-            root_dir,
-            output_file_path,
-            font_awesome_file_spec,
-            sp_icons_file_spec,
-            relevant_extensions: vec!["svg", "png", "jpg", "jpeg", "gif", "bmp", "ico"],
-            irrelevant_dir_names: vec![
-                    "bower_components",
-                    "node_modules",
-                    "vendor",
-                    "dist",
-                    "api",
-                    "build_release",
-                    "backup",
-                    "temp"
-                ],
-        })
-    }
-}
@@ -0,0 +1,97 @@
+use crate::{renderer::Renderer, ScanResult, VERSION_ID};
+
+// Renders a catalog that can be pasted in wikis and PR comments. The images are not embedded,
+// since their absolute local paths are meaningless on those platforms, only their names and paths are listed.
+pub struct MarkdownRenderer;
+
+impl Renderer for MarkdownRenderer {
+    fn get_extension(&self) -> &'static str {
+        "md"
+    }
+
+    fn render(&self, scan_result: &ScanResult) -> anyhow::Result<String> {
+        Ok(generate_markdown_as_string(scan_result))
+    }
+}
+
+pub fn generate_markdown_as_string(scan_result: &ScanResult) -> String {
+    let mut md = String::with_capacity(1000);
+    md += "# Image catalog\n\n";
+    md += &format!("Generated by img-dumper {} at {}, from `{}`\n\n",
+        VERSION_ID, scan_result.scanned_at.format("%d/%m/%Y - %H:%M:%S"), escape_code_span(&scan_result.root_dir));
+
+    let icon_sets: Vec<_> = scan_result.icon_sets.iter().filter(|icon_set| !icon_set.class_names.is_empty()).collect();
+
+    // projects that are only kept for their broken references have nothing to list
+    let project_dirs: Vec<_> = scan_result.projects.values().filter(|project_dir| !project_dir.images.is_empty()).collect();
+
+    md += "## Contents\n\n";
+    for icon_set in &icon_sets {
+        md += &format!("- {} ({} icons)\n", escape_text(&icon_set.title), icon_set.class_names.len());
+    }
+    for project_dir in &project_dirs {
+        md += &format!("- {} ({} images)\n", escape_text(&project_dir.name), project_dir.images.len());
+    }
+    md += "\n";
+
+    for icon_set in &icon_sets {
+        md += &format!("## {}\n\n", escape_text(&icon_set.title));
+        md += &format!("`{}` ---- class names are normally prefixed with `{}`\n\n",
+            escape_code_span(&icon_set.source_dir), escape_code_span(&icon_set.class_prefix));
+        let class_names: Vec<String> = icon_set.class_names.iter()
+            .map(|class| format!("`{}`", escape_code_span(class)))
+            .collect();
+        md += &class_names.join(", ");
+        md += "\n\n";
+    }
+
+    for project_dir in &project_dirs {
+        md += &format!("## {}\n\n", escape_text(&project_dir.name));
+        md += &format!("`{}`\n\n", escape_code_span(&project_dir.path));
+        md += "| Name | Extension | Path |\n";
+        md += "| --- | --- | --- |\n";
+        for image in &project_dir.images {
+            let relative_path = image.path.strip_prefix(&project_dir.path)
+                .map(|path| path.trim_start_matches('/'))
+                .unwrap_or(&image.path);
+            md += &format!("| {} | {} | `{}` |\n",
+                escape_table_cell(&escape_text(&image.name)),
+                escape_table_cell(&escape_text(&image.extension)),
+                escape_table_cell(&escape_code_span(relative_path)));
+        }
+        md += "\n";
+    }
+
+    md
+}
+
+// escapes the characters that would otherwise be interpreted as markdown inside normal text
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// code spans can't contain backticks, and a new line would end the paragraph
+fn escape_code_span(text: &str) -> String {
+    text.replace('`', "'").replace(['\n', '\r'], " ")
+}
+
+// inside a table, every unescaped pipe starts a new cell, even inside code spans
+fn escape_table_cell(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut previous = ' ';
+    for c in text.chars() {
+        if c == '|' && previous != '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        previous = c;
+    }
+    escaped
+}
@@ -1,60 +1,187 @@
+use std::{io::Write, sync::atomic::{AtomicBool, Ordering}};
+
 pub enum Argument {
     Dir,
     Target,
     Name,
     Basic,
+    Ext,
+    Exclude,
+    Format,
+    Output,
+    Similar,
+    Watch,
+    Port,
+    NoCache,
+    Jobs,
+    History,
+    Portable,
+    Bundle,
     Help,
 }
 
 impl Argument {
-    pub fn get_name(&self) -> &str {
+    pub fn get_name(&self) -> &'static str {
         match self {
             Argument::Dir      => "dir",
             Argument::Target   => "target",
             Argument::Name     => "name",
             Argument::Basic    => "basic",
+            Argument::Ext      => "ext",
+            Argument::Exclude  => "exclude",
+            Argument::Format   => "format",
+            Argument::Output   => "output",
+            Argument::Similar  => "similar",
+            Argument::Watch    => "watch",
+            Argument::Port     => "port",
+            Argument::NoCache  => "no-cache",
+            Argument::Jobs     => "jobs",
+            Argument::History  => "history",
+            Argument::Portable => "portable",
+            Argument::Bundle   => "bundle",
             Argument::Help     => "help",
         }
     }
 
-    pub fn get_help_msg(&self) -> &str {
-        // weird formatting as a simple solution to keep the indentation when printing.
+    // None for the arguments that only have a long form
+    pub fn get_short(&self) -> Option<char> {
         match self {
-Argument::Dir => "--dir
-    1 argument, the path (relative or absolute) to the root directory.
-    The path can contain whitespaces, no need to surround it with quatation marks.
-
-    Every top level directory inside the root directory, is considered a project.
-",
-Argument::Target => "--target 
-    1 argument, the path (relative or absolute) to the directory that the generated file will be saved.
-    The path can contain whitespaces, no need to surround it with quatation marks.
-
-",
-Argument::Name => "--name 
-    1 argument, the name of the generated file.
-    The name can contain whitespaces.
-
-",
-Argument::Basic => "--basic 
-    No argument
-    Skips the parsing of special css files, that may not exist if the project is used for generic use.
-
-",
-_  => "",
+            Argument::Dir      => Some('d'),
+            Argument::Target   => Some('t'),
+            Argument::Name     => Some('n'),
+            Argument::Basic    => Some('b'),
+            Argument::Ext      => Some('e'),
+            Argument::Exclude  => Some('x'),
+            Argument::Format   => Some('f'),
+            Argument::Output   => Some('o'),
+            Argument::Similar  => Some('s'),
+            Argument::Watch    => Some('w'),
+            Argument::Port     => Some('p'),
+            Argument::NoCache  => None,
+            Argument::Jobs     => Some('j'),
+            Argument::History  => None,
+            Argument::Portable => None,
+            Argument::Bundle   => None,
+            Argument::Help     => Some('h'),
+        }
     }
+
+    // one line description, displayed by `-h`
+    pub fn get_help_msg(&self) -> &'static str {
+        match self {
+            Argument::Dir      => "Path to the root directory, every top level directory inside it is a project",
+            Argument::Target   => "Path to the directory that the generated file will be saved",
+            Argument::Name     => "Name of the generated file, without extension",
+            Argument::Basic    => "Skip the parsing of special css files",
+            Argument::Ext      => "Only search for this extension (repeatable)",
+            Argument::Exclude  => "Also ignore directories with this name (repeatable)",
+            Argument::Format   => "Format of the generated file",
+            Argument::Output   => "Full path of the generated file, `-` for stdout",
+            Argument::Similar  => "Also group visually similar images, optionally with the max number of differing hash bits",
+            Argument::Watch    => "Keep running and regenerate the report whenever a project changes",
+            Argument::Port     => "Port of the local server of the `serve` command",
+            Argument::NoCache  => "Read every image again, without using or updating the scan cache",
+            Argument::Jobs     => "Number of threads that traverse the projects and read the files",
+            Argument::History  => "Save a snapshot of every scan in this directory, and mark the images that are new since the previous one",
+            Argument::Portable => "Embed the images and the icon fonts in the html report, so that it can be opened anywhere",
+            Argument::Bundle   => "Write the html report as index.html in this directory, with a copy of the images and the icon fonts next to it",
+            Argument::Help     => "Print help (see more with '--help')",
+        }
+    }
+
+    // full description, displayed by `--help`
+    pub fn get_long_help_msg(&self) -> &'static str {
+        // weird formatting as a simple solution to keep the indentation when printing.
+        match self {
+Argument::Dir => "1 argument, the path (relative or absolute) to the root directory.
+Paths that contain whitespaces should be surrounded with quotation marks, the words of an unquoted one are joined with a space.
+
+Every top level directory inside the root directory, is considered a project.",
+Argument::Target => "1 argument, the path (relative or absolute) to the directory that the generated file will be saved.
+Paths that contain whitespaces should be surrounded with quotation marks, the words of an unquoted one are joined with a space.",
+Argument::Name => "1 argument, the name of the generated file, without extension.",
+Argument::Basic => "No argument.
+Skips the parsing of special css files, that may not exist if the project is used for generic use.",
+Argument::Ext => "1 argument, an extension to search for, like `svg` (the leading dot is optional).
+Can be repeated, or given as a comma separated list. When given, it replaces the extensions of the defaults and of the config file.",
+Argument::Exclude => "1 argument, a directory name that should be ignored in each project, like `coverage`.
+Can be repeated, or given as a comma separated list. It is added on top of the defaults and of the config file.",
+Argument::Format => "1 argument, the format of the generated file: `html` (default), `json`, `markdown` or `csv`.
+The json format follows the documented schema of the README, so that other tools can consume the scan result.
+The markdown format is a catalog for wikis and PR comments, and the csv format has one row per image.",
+Argument::Output => "1 argument, the full path (relative or absolute) of the generated file, including the extension.
+Overrides --target and --name. Use `-` to write to stdout, in which case progress messages are printed to stderr
+and the generated file is not opened.",
+Argument::Similar => "0 or 1 argument, the max number of bits (0-64) that the perceptual hashes of two images may differ in,
+for them to be considered similar. Defaults to 10 when only the flag is given.
+Decodes png, jpeg, gif and bmp files and rasterizes svg files, so that images that look alike, like the same icon
+exported in different sizes, are displayed side by side in the report. Makes the scan slower.",
+Argument::Watch => "No argument.
+After generating the report, keeps running and watches the root directory (except the ignored and the hidden folders).
+When images or source files change, only the affected projects are traversed again and the report is rewritten.
+Can't be combined with `--output -` or with the other commands. Stop it with Ctrl+C.",
+Argument::Port => "1 argument, the port that the `serve` command listens to, on 127.0.0.1. Defaults to 8470.",
+Argument::NoCache => "No argument.
+The formats, dimensions and hashes of the images are kept in a cache file of the user cache directory, one per root directory,
+so that the next scans only read the images whose size or modification time changed. This flag ignores the cache
+and leaves it as it is, for when it is suspected to be wrong.",
+Argument::Jobs => "1 argument, the number of threads that traverse the projects and read, hash and search the files.
+Defaults to the number of logical cores. `--jobs 1` does everything on a single thread, the result is the same either way.",
+Argument::History => "1 argument, the path (relative or absolute) to a directory that keeps a json snapshot of every scan.
+The images that appeared since the previous snapshot are marked as new in the report, and the search bar gets an `only new` filter.
+Only for the `scan` (default) and the `serve` commands.",
+Argument::Portable => "No argument.
+Embeds every image in the html report as a data uri, and the font files of the icon sets in its css, so that the single file
+can be sent to someone else. Rasters over 32 KB are embedded as thumbnails of up to 256 px. A warning is printed when the file
+gets too big to be sent by email. Only for the html report of the `scan` (default) command.",
+Argument::Bundle => "1 argument, the path (relative or absolute) to a directory, that is created if needed.
+Writes the html report as `index.html` in it, and copies every image and every font file of the icon sets to its `assets/`
+directory, with relative links, so that the directory can be published on a static host or committed as it is.
+The images are copied to `assets/images/<project>/`, and a file whose name is taken already gets a `-2`, `-3`... suffix.
+Nothing is deleted from the directory. Replaces --target and --name, and is only for the html report of the `scan` (default)
+command, without --output or --portable.",
+Argument::Help => "Overrides normal program execution and just displays an informative message on the terminal.",
+        }
     }
 }
 
-pub fn print_whole_help_message() {
-    let mut msg = 
-    "Running the program without arguments will try to find the common default installation paths for xampp/htdocs
-and consider this path as root.\n\n".to_owned();
+pub fn get_program_description() -> &'static str {
+    "Searches for image files inside a root directory and generates an interactive .html file that displays them.
+
+Running the program without arguments will try to find the common default installation paths for xampp/htdocs
+and consider this path as root."
+}
+
+static STATUS_TO_STDERR: AtomicBool = AtomicBool::new(false);
 
-    msg += Argument::Dir.get_help_msg();
-    msg += Argument::Target.get_help_msg();
-    msg += Argument::Name.get_help_msg();
-    msg += Argument::Basic.get_help_msg();
+// Progress messages normally go to stdout, but when the generated output itself is written to stdout
+// they are moved to stderr, so that they can't corrupt it.
+pub fn set_status_to_stderr(to_stderr: bool) {
+    STATUS_TO_STDERR.store(to_stderr, Ordering::Relaxed);
+}
 
-    print!("{}",msg);
+pub fn write_status(args: std::fmt::Arguments) {
+    if STATUS_TO_STDERR.load(Ordering::Relaxed) {
+        let mut stderr = std::io::stderr().lock();
+        let _ = stderr.write_fmt(args);
+        let _ = stderr.flush();
+    } else {
+        let mut stdout = std::io::stdout().lock();
+        let _ = stdout.write_fmt(args);
+        let _ = stdout.flush();
+    }
 }
+
+// same as print!, but respecting `set_status_to_stderr`
+macro_rules! status {
+    ($($arg:tt)*) => { $crate::message_printer::write_status(format_args!($($arg)*)) };
+}
+
+// same as println!, but respecting `set_status_to_stderr`
+macro_rules! statusln {
+    () => { $crate::message_printer::write_status(format_args!("\n")) };
+    ($($arg:tt)*) => { $crate::message_printer::write_status(format_args!("{}\n", format_args!($($arg)*))) };
+}
+
+pub(crate) use status;
+pub(crate) use statusln;
@@ -0,0 +1,124 @@
+use std::{collections::{BTreeMap, HashMap}, fs, io::Cursor, path::Path};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::DynamicImage;
+use rayon::prelude::*;
+
+use crate::{css_parser::{rewrite_urls, split_url_suffix}, html_escape::decode_url_path, image_metadata::ImageFormat, similarity::rasterize_svg,
+    utils::get_mime_type, IconSet, Img, ProjectDir};
+
+// rasters that are bigger than this are embedded as a thumbnail, if it is smaller
+const THUMBNAIL_MIN_BYTES: u64 = 32 * 1024;
+
+// the longest side of a thumbnail, in pixels. The tiles are much smaller, the details panel is about this big.
+const THUMBNAIL_MAX_SIZE: u32 = 256;
+
+// the longest side of the thumbnails that the snapshots keep, as big as the thumbnails of the diff page
+const SNAPSHOT_THUMBNAIL_MAX_SIZE: u32 = 64;
+
+// The data uri of every image of the projects, keyed by path, computed in parallel.
+// The images that can't be read are left out, so that they keep pointing at their file.
+pub fn make_image_data_uris(projects: &BTreeMap<String, ProjectDir>) -> HashMap<String, String> {
+    let images: Vec<&Img> = projects.values().flat_map(|project_dir| project_dir.images.iter()).collect();
+    images.par_iter()
+        .filter_map(|img| make_image_data_uri(img).map(|data_uri| (img.path.clone(), data_uri)))
+        .collect()
+}
+
+// The content of the image as a data uri, or of a png thumbnail of it when it is a big raster
+pub fn make_image_data_uri(img: &Img) -> Option<String> {
+    let bytes = fs::read(&img.path).ok()?;
+    let is_raster = matches!(img.header.format, Some(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::Bmp));
+    if is_raster && bytes.len() as u64 > THUMBNAIL_MIN_BYTES {
+        if let Some(thumbnail) = make_thumbnail(&bytes).filter(|thumbnail| thumbnail.len() < bytes.len()) {
+            return Some(encode_data_uri("image/png", &thumbnail));
+        }
+    }
+
+    let mime_type = match img.header.format {
+        Some(format) => get_mime_type(format.get_name()),
+        None => get_mime_type(&img.extension),
+    };
+    Some(encode_data_uri(mime_type, &bytes))
+}
+
+// The png thumbnail of every image of the projects as a data uri, keyed by path, computed in parallel.
+// The json export keeps them, so that the diff can show the images that were modified or removed since.
+// The images that can't be decoded, like the icos, are left out.
+pub fn make_snapshot_thumbnails(projects: &BTreeMap<String, ProjectDir>) -> HashMap<String, String> {
+    let images: Vec<&Img> = projects.values().flat_map(|project_dir| project_dir.images.iter()).collect();
+    images.par_iter()
+        .filter_map(|img| make_snapshot_thumbnail(img).map(|data_uri| (img.path.clone(), data_uri)))
+        .collect()
+}
+
+fn make_snapshot_thumbnail(img: &Img) -> Option<String> {
+    let image = match img.header.format? {
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::Bmp => image::open(&img.path).ok()?,
+        ImageFormat::Svg => DynamicImage::ImageRgba8(rasterize_svg(Path::new(&img.path))?),
+        ImageFormat::Ico => return None,
+    };
+    let png = encode_thumbnail(image, SNAPSHOT_THUMBNAIL_MAX_SIZE)?;
+    Some(encode_data_uri("image/png", &png))
+}
+
+// The content of any file as a data uri, with the type of its extension. None if it can't be read.
+pub fn make_file_data_uri(path: &str) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    let extension = Path::new(path).extension().and_then(|extension| extension.to_str()).unwrap_or_default().to_lowercase();
+    Some(encode_data_uri(get_mime_type(&extension), &bytes))
+}
+
+// The data uri of every local file that the css of the icon sets points at, like their fonts, keyed by path
+pub fn make_font_data_uris(icon_sets: &[IconSet]) -> HashMap<String, String> {
+    icon_sets.iter()
+        .flat_map(|icon_set| get_css_file_paths(&icon_set.css))
+        .filter_map(|path| make_file_data_uri(&path).map(|data_uri| (path, data_uri)))
+        .collect()
+}
+
+// The local files that the `url(...)` of the css point at, without their query and fragment
+pub fn get_css_file_paths(css: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    rewrite_css_urls(css, |path| {
+        paths.push(path.to_owned());
+        None
+    });
+    paths
+}
+
+// Replaces the `url(...)` of the css that point at local files with what `get_new_url` returns for their path.
+// The query is dropped and the fragment is kept, since svg fonts are selected by it. The urls that are not absolute
+// paths, like data uris and remote urls, and the paths that `get_new_url` returns None for stay as they are.
+pub fn rewrite_css_urls(css: &str, mut get_new_url: impl FnMut(&str) -> Option<String>) -> String {
+    rewrite_urls(css, |url| {
+        if !url.starts_with('/') || url.starts_with("//") {
+            return None;
+        }
+        let (url_path, suffix) = split_url_suffix(url);
+        let fragment = suffix.find('#').map_or("", |index| &suffix[index..]);
+        get_new_url(&decode_url_path(url_path)).map(|new_url| format!("{}{}", new_url, fragment))
+    })
+}
+
+fn make_thumbnail(bytes: &[u8]) -> Option<Vec<u8>> {
+    encode_thumbnail(image::load_from_memory(bytes).ok()?, THUMBNAIL_MAX_SIZE)
+}
+
+// the image as a png, scaled down to fit in `max_size` pixels when it is bigger
+fn encode_thumbnail(image: DynamicImage, max_size: u32) -> Option<Vec<u8>> {
+    let thumbnail = if image.width() > max_size || image.height() > max_size {
+        image.thumbnail(max_size, max_size)
+    } else {
+        image
+    };
+    let mut png = Vec::new();
+    thumbnail.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png).ok()?;
+    Some(png)
+}
+
+fn encode_data_uri(mime_type: &str, bytes: &[u8]) -> String {
+    // the charset of the text types is not part of the data uri
+    let mime_type = mime_type.split(';').next().unwrap_or(mime_type);
+    format!("data:{};base64,{}", mime_type, STANDARD.encode(bytes))
+}
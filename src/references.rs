@@ -0,0 +1,458 @@
+use std::{collections::{BTreeMap, HashMap}, fs, path::Path};
+
+use rayon::prelude::*;
+
+use crate::{utils::normalize_path, Img, ProjectDir};
+
+// extensions of the files that are searched for references to images
+pub const SOURCE_EXTENSIONS: &[&str] = &["html", "php", "js", "ts", "css", "scss", "vue", "jsx", "json"];
+
+// bigger files are most probably generated bundles, and are not searched
+const MAX_SOURCE_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+// the snippet of the line that contains the reference is cut to this many characters
+const MAX_SNIPPET_CHARS: usize = 160;
+
+// characters that can't be part of a referenced path, so they mark where it starts
+const PATH_DELIMITERS: &[u8] = b" \t\r\n'\"`()<>,;:=[]{}|*!+";
+
+// A place in a source file that points at an image
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SourceReference {
+    // the file that contains the reference
+    pub source_path: String,
+    // 1-based
+    pub line: usize,
+    // the path as it is written, like `../img/logo.png`
+    pub target: String,
+    // the trimmed line, cut if it is too long
+    pub snippet: String,
+    // the path is glued to an expression, like `${base}/logo.png`, so only its file name is meaningful
+    pub is_partial: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrokenReferenceKind {
+    // nothing exists at the path
+    Missing,
+    // the image exists with a different letter case, which works on windows but not on linux servers
+    CaseMismatch { actual_path: String },
+}
+
+impl BrokenReferenceKind {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            BrokenReferenceKind::Missing => "missing",
+            BrokenReferenceKind::CaseMismatch { .. } => "case mismatch",
+        }
+    }
+}
+
+// A reference of a source file to an image that doesn't exist
+#[derive(Debug, Clone)]
+pub struct BrokenReference {
+    pub reference: SourceReference,
+    pub kind: BrokenReferenceKind,
+}
+
+// Searches the source files of every project for the paths and names of the images, and fills `Img::references`
+// and `ProjectDir::broken_references`.
+// A reference is resolved relative to the file that contains it and to its project (or to the root directory,
+// when it starts with `/`). When it doesn't resolve to an image, like with bundler aliases (`@/assets/logo.png`),
+// the images of the same project with its file name are counted as used. It is still broken when nothing exists
+// at the paths it resolves to, and a case mismatch when one of them is the path of an image in another letter case.
+// A path glued to an expression can't be resolved, so it is never broken. The paths inside the comments of the source files are not references.
+pub fn find_references(projects: &mut BTreeMap<String, ProjectDir>, root_dir: &str, extensions: &[String]) {
+    let source_references = projects.par_iter()
+        .map(|(project_name, project_dir)| (project_name.clone(), read_project_references(project_dir, extensions)))
+        .collect();
+    resolve_references(projects, &source_references, root_dir);
+}
+
+// The references written in the source files of the project, before they are resolved. Reading them is the slow part
+// of `find_references`, so they can be kept for the projects that didn't change.
+pub fn read_project_references(project_dir: &ProjectDir, extensions: &[String]) -> Vec<SourceReference> {
+    project_dir.source_files.par_iter()
+        .flat_map_iter(|source_path| read_references(source_path, extensions))
+        .collect()
+}
+
+// The rest of `find_references`, with what `read_project_references` returned for each project, by project name.
+// The references are resolved against the images of all the projects, since they can point at any of them.
+pub fn resolve_references(projects: &mut BTreeMap<String, ProjectDir>, source_references: &BTreeMap<String, Vec<SourceReference>>,
+        root_dir: &str) {
+    let raw_references: Vec<(String, String, SourceReference)> = projects.values()
+        .flat_map(|project_dir| source_references.get(&project_dir.name).into_iter().flatten()
+            .map(|reference| (project_dir.name.clone(), project_dir.path.clone(), reference.clone())))
+        .collect();
+
+    let mut images_by_path: HashMap<String, (String, usize)> = HashMap::new();
+    let mut images_by_lowercase_path: HashMap<String, (String, usize)> = HashMap::new();
+    let mut images_by_file_name: HashMap<(String, String), Vec<usize>> = HashMap::new();
+    for project_dir in projects.values() {
+        for (i, img) in project_dir.images.iter().enumerate() {
+            images_by_path.insert(img.path.clone(), (project_dir.name.clone(), i));
+            images_by_lowercase_path.insert(img.path.to_lowercase(), (project_dir.name.clone(), i));
+            images_by_file_name.entry((project_dir.name.clone(), get_file_name(&img.path).to_lowercase())).or_default().push(i);
+        }
+    }
+
+    let mut references: HashMap<(String, usize), Vec<SourceReference>> = HashMap::new();
+    let mut broken_references: HashMap<String, Vec<BrokenReference>> = HashMap::new();
+    for (project_name, project_path, reference) in raw_references {
+        let Some(target) = get_local_target(&reference.target) else { continue };
+        let candidates = get_candidate_paths(&target, &reference.source_path, &project_path, root_dir);
+        if !reference.is_partial {
+            if let Some(key) = candidates.iter().find_map(|candidate| images_by_path.get(candidate).cloned()) {
+                references.entry(key).or_default().push(reference);
+                continue;
+            }
+            // it is still a use of the image, even if it only works on case insensitive file systems
+            if let Some(key) = candidates.iter().find_map(|candidate| images_by_lowercase_path.get(&candidate.to_lowercase()).cloned()) {
+                let actual_path = projects[&key.0].images[key.1].path.clone();
+                references.entry(key).or_default().push(reference.clone());
+                broken_references.entry(project_name).or_default()
+                    .push(BrokenReference { reference, kind: BrokenReferenceKind::CaseMismatch { actual_path } });
+                continue;
+            }
+        }
+
+        let file_name = get_file_name(&target);
+        let file_name_key = (project_name, file_name.to_lowercase());
+        let same_name_indexes = images_by_file_name.get(&file_name_key).map(|indexes| indexes.as_slice()).unwrap_or_default();
+        for &i in same_name_indexes {
+            references.entry((file_name_key.0.clone(), i)).or_default().push(reference.clone());
+        }
+
+        // an image with the same name in another directory is a different file, the path itself is dangling
+        if reference.is_partial || candidates.iter().any(|candidate| Path::new(candidate).exists()) {
+            continue;
+        }
+        broken_references.entry(file_name_key.0).or_default().push(BrokenReference { reference, kind: BrokenReferenceKind::Missing });
+    }
+
+    for project_dir in projects.values_mut() {
+        let mut project_broken_references = broken_references.remove(&project_dir.name).unwrap_or_default();
+        project_broken_references.sort_by(|a, b| a.reference.cmp(&b.reference));
+        project_dir.broken_references = project_broken_references;
+
+        for (i, img) in project_dir.images.iter_mut().enumerate() {
+            let mut img_references = references.remove(&(project_dir.name.clone(), i)).unwrap_or_default();
+            img_references.sort();
+            img_references.dedup();
+            img.references = Some(img_references);
+        }
+    }
+}
+
+// Images that no source file points at, in the order of the projects. Empty when the references were not searched for.
+pub fn get_unused_images(projects: &BTreeMap<String, ProjectDir>) -> Vec<(&ProjectDir, &Img)> {
+    projects.values()
+        .flat_map(|project_dir| project_dir.images.iter()
+            .filter(|img| img.is_unused())
+            .map(move |img| (project_dir, img)))
+        .collect()
+}
+
+fn read_references(source_path: &str, extensions: &[String]) -> Vec<SourceReference> {
+    let too_big = fs::metadata(source_path).map(|metadata| metadata.len() > MAX_SOURCE_FILE_BYTES).unwrap_or(true);
+    if too_big {
+        return Vec::new();
+    }
+    let Ok(bytes) = fs::read(source_path) else { return Vec::new() };
+    let contents = String::from_utf8_lossy(&bytes);
+    let extension = Path::new(source_path).extension().and_then(|extension| extension.to_str()).unwrap_or_default().to_lowercase();
+    let code = blank_out_comments(&contents, CommentSyntax::of_extension(&extension));
+
+    let mut references = Vec::new();
+    for (i, (line, code_line)) in contents.lines().zip(code.lines()).enumerate() {
+        for (target, is_partial) in extract_image_paths(code_line, extensions) {
+            references.push(SourceReference {
+                source_path: source_path.to_owned(),
+                line: i + 1,
+                target,
+                snippet: get_snippet(line),
+                is_partial,
+            });
+        }
+    }
+
+    references
+}
+
+// Finds everything in the line that looks like a path to a file with one of the extensions, like the `img/logo.png`
+// of `src="img/logo.png?v=2"`. The query and the fragment are not part of the returned paths.
+// Each path comes with whether it is only the end of a path that is built at runtime, like the `/logo.png`
+// of `${base}/logo.png`, `'img/' + dir + '/logo.png'`, `{{ base }}/logo.png` or `<?= $base ?>/logo.png`.
+pub fn extract_image_paths(line: &str, extensions: &[String]) -> Vec<(String, bool)> {
+    let lowercase_line = line.to_ascii_lowercase();
+    let bytes = line.as_bytes();
+    let mut paths = Vec::new();
+    for extension in extensions {
+        let suffix = format!(".{}", extension);
+        for (dot_index, _) in lowercase_line.match_indices(&suffix) {
+            let end = dot_index + suffix.len();
+            if bytes.get(end).is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'_') {
+                continue;
+            }
+            let start = bytes[..dot_index].iter().rposition(|b| PATH_DELIMITERS.contains(b)).map(|i| i + 1).unwrap_or(0);
+            let path = line[start..end].replace("\\/", "/").replace('\\', "/");
+            // `*.png` or a sentence that ends with `.png`, there is no file name
+            if path.len() == suffix.len() || path.ends_with(&format!("/{}", suffix)) {
+                continue;
+            }
+            let before = line[..start].trim_end_matches([' ', '\'', '"', '`']);
+            let is_partial = before.ends_with(['}', '+']) || before.ends_with("?>");
+            paths.push((path, is_partial));
+        }
+    }
+
+    paths
+}
+
+// The comments that a source file can have, which point at images that are not used anymore
+#[derive(Debug, Clone, Copy, Default)]
+struct CommentSyntax {
+    // `<!-- -->`
+    has_html_comments: bool,
+    // `/* */`
+    has_block_comments: bool,
+    // `//` up to the end of the line
+    has_line_comments: bool,
+    // a comment can't start inside a quoted string, where `/*` can be part of a glob like `'img/*.png'`
+    has_strings: bool,
+}
+
+impl CommentSyntax {
+    fn of_extension(extension: &str) -> Self {
+        let (has_html_comments, has_block_comments, has_line_comments, has_strings) = match extension {
+            "html" => (true, false, false, false),
+            "php" | "vue" => (true, true, true, true),
+            "js" | "ts" | "jsx" | "scss" => (false, true, true, true),
+            "css" => (false, true, false, true),
+            _ => (false, false, false, false),
+        };
+        Self { has_html_comments, has_block_comments, has_line_comments, has_strings }
+    }
+}
+
+// Replaces the characters of the comments with spaces, keeping the line breaks so that the lines keep their number.
+// A quoted string ends with its line, except for a template literal, so that an apostrophe of a sentence in the
+// html of a php or vue file can't hide the comments of the next lines. A `//` that follows a `:` is a url, not a comment.
+fn blank_out_comments(contents: &str, syntax: CommentSyntax) -> String {
+    let chars: Vec<char> = contents.chars().collect();
+    let starts_with = |i: usize, pattern: &str| pattern.chars().enumerate().all(|(offset, c)| chars.get(i + offset) == Some(&c));
+    let mut code = String::with_capacity(contents.len());
+    let mut quote: Option<char> = None;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if let Some(open_quote) = quote {
+            if c == '\\' && i + 1 < chars.len() {
+                code.push(c);
+                code.push(chars[i + 1]);
+                i += 2;
+                continue;
+            }
+            if c == open_quote || (c == '\n' && open_quote != '`') {
+                quote = None;
+            }
+            code.push(c);
+            i += 1;
+            continue;
+        }
+
+        let comment_end = if syntax.has_html_comments && starts_with(i, "<!--") {
+            Some(find_after(&chars, i + 4, "-->"))
+        } else if syntax.has_block_comments && starts_with(i, "/*") {
+            Some(find_after(&chars, i + 2, "*/"))
+        } else if syntax.has_line_comments && starts_with(i, "//") && (i == 0 || chars[i - 1] != ':') {
+            Some((i..chars.len()).find(|&index| chars[index] == '\n').unwrap_or(chars.len()))
+        } else {
+            None
+        };
+        match comment_end {
+            Some(end) => {
+                code.extend(chars[i..end].iter().map(|&c| if c == '\n' { '\n' } else { ' ' }));
+                i = end;
+            }
+            None => {
+                if syntax.has_strings && matches!(c, '\'' | '"' | '`') {
+                    quote = Some(c);
+                }
+                code.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    code
+}
+
+// the index after the first `pattern` from `start`, or the end when there is none
+fn find_after(chars: &[char], start: usize, pattern: &str) -> usize {
+    let pattern: Vec<char> = pattern.chars().collect();
+    (start..chars.len()).find(|&i| chars[i..].starts_with(&pattern)).map_or(chars.len(), |i| i + pattern.len())
+}
+
+// None for urls that don't point to a file of the root directory, like remote urls or data uris
+fn get_local_target(target: &str) -> Option<String> {
+    if target.starts_with("//") || target.contains("://") || target.starts_with("data:") {
+        return None;
+    }
+    Some(percent_encoding::percent_decode_str(target).decode_utf8_lossy().into_owned())
+}
+
+fn get_candidate_paths(target: &str, source_path: &str, project_path: &str, root_dir: &str) -> Vec<String> {
+    if let Some(rooted) = target.strip_prefix('/') {
+        return vec![normalize_path(&format!("{}/{}", project_path, rooted)), normalize_path(&format!("{}/{}", root_dir, rooted))];
+    }
+    let source_dir = source_path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or(project_path);
+    vec![normalize_path(&format!("{}/{}", source_dir, target)), normalize_path(&format!("{}/{}", project_path, target))]
+}
+
+fn get_file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn get_snippet(line: &str) -> String {
+    let line = line.trim();
+    if line.chars().count() <= MAX_SNIPPET_CHARS {
+        return line.to_owned();
+    }
+    let mut snippet: String = line.chars().take(MAX_SNIPPET_CHARS).collect();
+    snippet.push('…');
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A project with the images and the source files, in a new directory of the temp dir. Returns it with its references searched for.
+    fn make_project(name: &str, image_paths: &[&str], source_files: &[(&str, &str)]) -> ProjectDir {
+        let project_path = std::env::temp_dir().join(format!("img-dumper-references-{}-{}", std::process::id(), name))
+            .to_string_lossy().replace('\\', "/");
+        let _ = fs::remove_dir_all(&project_path);
+        let write = |relative_path: &str, contents: &str| {
+            let path = format!("{}/{}", project_path, relative_path);
+            fs::create_dir_all(Path::new(&path).parent().unwrap()).unwrap();
+            fs::write(&path, contents).unwrap();
+            path
+        };
+        let images = image_paths.iter()
+            .map(|relative_path| Img { name: get_file_name(relative_path).to_owned(), path: write(relative_path, "image"), ..Img::default() })
+            .collect();
+        let source_files = source_files.iter().map(|(relative_path, contents)| write(relative_path, contents)).collect();
+
+        let project_dir = ProjectDir { name: name.to_owned(), path: project_path.clone(), images, source_files, ..ProjectDir::default() };
+        let mut projects = BTreeMap::from([(name.to_owned(), project_dir)]);
+        find_references(&mut projects, &project_path, &["png".to_owned(), "svg".to_owned()]);
+        fs::remove_dir_all(&project_path).unwrap();
+        projects.remove(name).unwrap()
+    }
+
+    fn get_used_names(project_dir: &ProjectDir) -> Vec<&str> {
+        project_dir.images.iter().filter(|img| !img.is_unused()).map(|img| img.name.as_str()).collect()
+    }
+
+    #[test]
+    fn paths_are_extracted_with_whether_they_are_partial() {
+        let extensions = ["png".to_owned(), "svg".to_owned()];
+        assert_eq!(extract_image_paths("<img src=\"img/logo.png?v=2\" alt=\"\">", &extensions), vec![("img/logo.png".to_owned(), false)]);
+        assert_eq!(extract_image_paths(".a { background: url('../img/bg.SVG#x') }", &extensions), vec![("../img/bg.SVG".to_owned(), false)]);
+        assert_eq!(extract_image_paths("const icon = `${base}/icon.svg`;", &extensions), vec![("/icon.svg".to_owned(), true)]);
+        assert_eq!(extract_image_paths("'img/' + dir + '/a.png'", &extensions), vec![("/a.png".to_owned(), true)]);
+        // not a file name
+        assert!(extract_image_paths("Drop a .png here, or any *.svg, but not a.pngx", &extensions).is_empty());
+    }
+
+    #[test]
+    fn references_of_every_source_type_mark_the_images_as_used() {
+        let project_dir = make_project("used", &["img/html.png", "img/css.svg", "img/js.png", "img/jsx.png", "img/logo.png"], &[
+            ("index.html", "<body>\n  <img src=\"img/html.png\">\n</body>"),
+            ("css/style.css", ".a { background: url(../img/css.svg); }"),
+            ("js/app.js", "const urls = ['https://cdn.example.com/js.png', '../img/js.png'];"),
+            ("App.jsx", "import icon from './img/jsx.png';\nexport const App = () => <img src={icon} />;"),
+            // a different file that ends with the same name
+            ("other.js", "const a = 'img/mylogo.png';"),
+        ]);
+        assert_eq!(get_used_names(&project_dir), vec!["html.png", "css.svg", "js.png", "jsx.png"]);
+    }
+
+    #[test]
+    fn commented_out_references_do_not_count() {
+        let project_dir = make_project("commented", &["img/a.png", "img/b.png", "img/c.png", "img/d.png", "img/e.png", "img/used.png"], &[
+            ("index.html", "<!-- <img src=\"img/a.png\">\n<img src=\"img/b.png\"> -->\n<p>Don't forget</p> <img src=\"img/used.png\">"),
+            ("style.css", "/* .old { background: url(img/c.png); } */ .a { content: 'img/*.png'; }"),
+            ("app.js", "// const d = 'img/d.png';\nconst url = 'http://example.com/x'; /* 'img/e.png' */"),
+        ]);
+        assert_eq!(get_used_names(&project_dir), vec!["used.png"]);
+        assert!(project_dir.broken_references.is_empty());
+    }
+
+    fn get_broken(project_dir: &ProjectDir) -> Vec<(&str, BrokenReferenceKind)> {
+        project_dir.broken_references.iter()
+            .map(|broken_reference| (broken_reference.reference.target.as_str(), broken_reference.kind.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn dangling_paths_are_missing_even_when_the_name_exists_elsewhere() {
+        let project_dir = make_project("broken", &["a/logo.png", "b/logo.png", "b/Icon.png"], &[
+            ("index.html", "<img src=\"a/logo.png\"> <img src=\"img/old/logo.png\"> <img src=\"b/icon.png\">\n\
+                <img src=\"a/Icon.png\"> <img src=\"a/gone.png\"> <img src=\"${base}/logo.png\">"),
+        ]);
+        let icon_path = project_dir.images.iter().find(|img| img.name == "Icon.png").unwrap().path.clone();
+        assert_eq!(get_broken(&project_dir), vec![
+            ("b/icon.png", BrokenReferenceKind::CaseMismatch { actual_path: icon_path }),
+            // the same name in another directory, it is still a use of the images with that name
+            ("img/old/logo.png", BrokenReferenceKind::Missing),
+            // `b/Icon.png` is a different file, not the same one with another case
+            ("a/Icon.png", BrokenReferenceKind::Missing),
+            ("a/gone.png", BrokenReferenceKind::Missing),
+        ]);
+        assert_eq!(get_used_names(&project_dir), vec!["logo.png", "logo.png", "Icon.png"]);
+    }
+
+    #[test]
+    fn comments_are_blanked_out_without_moving_the_lines() {
+        let js = CommentSyntax::of_extension("js");
+        assert_eq!(blank_out_comments("a('//x.png'); // b.png\n/* c\nd */ e", js), "a('//x.png');         \n    \n     e");
+        assert_eq!(blank_out_comments("url(http://x/a.png)", CommentSyntax::of_extension("scss")), "url(http://x/a.png)");
+        assert_eq!(blank_out_comments("{\"a\": \"// b.png\"}", CommentSyntax::of_extension("json")), "{\"a\": \"// b.png\"}");
+    }
+
+    #[test]
+    fn kept_references_are_resolved_against_the_current_images() {
+        let root_dir = std::env::temp_dir().join(format!("img-dumper-references-{}-kept", std::process::id()))
+            .to_string_lossy().replace('\\', "/");
+        let _ = fs::remove_dir_all(&root_dir);
+        let source_path = format!("{}/site/index.html", root_dir);
+        let image_path = format!("{}/shared/logo.png", root_dir);
+        for (path, contents) in [(&source_path, "<img src=\"/shared/logo.png\">"), (&image_path, "image")] {
+            fs::create_dir_all(Path::new(path).parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        let site = ProjectDir { name: "site".to_owned(), path: format!("{}/site", root_dir), source_files: vec![source_path.clone()],
+            ..ProjectDir::default() };
+        let shared = ProjectDir { name: "shared".to_owned(), path: format!("{}/shared", root_dir),
+            images: vec![Img { name: "logo.png".to_owned(), path: image_path.clone(), ..Img::default() }], ..ProjectDir::default() };
+        let mut projects = BTreeMap::from([("site".to_owned(), site), ("shared".to_owned(), shared)]);
+        let source_references = projects.iter()
+            .map(|(name, project_dir)| (name.clone(), read_project_references(project_dir, &["png".to_owned()])))
+            .collect();
+        // the source file is not read again
+        fs::remove_file(&source_path).unwrap();
+
+        resolve_references(&mut projects, &source_references, &root_dir);
+        assert_eq!(get_used_names(&projects["shared"]), vec!["logo.png"]);
+        assert!(projects["site"].broken_references.is_empty());
+
+        fs::remove_file(&image_path).unwrap();
+        projects.get_mut("shared").unwrap().images.clear();
+        resolve_references(&mut projects, &source_references, &root_dir);
+        assert_eq!(get_broken(&projects["site"]), vec![("/shared/logo.png", BrokenReferenceKind::Missing)]);
+        fs::remove_dir_all(&root_dir).unwrap();
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{csv_report::CsvRenderer, html_report::HtmlRenderer, json_export::JsonRenderer, markdown_report::MarkdownRenderer, ScanResult};
+
+// A consumer of the scan result that turns it into the contents of a single file.
+pub trait Renderer {
+    // the extension of the generated file, without the dot
+    fn get_extension(&self) -> &'static str;
+
+    fn render(&self, scan_result: &ScanResult) -> anyhow::Result<String>;
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Html,
+    Json,
+    Markdown,
+    Csv,
+}
+
+impl OutputFormat {
+    pub const ALL: &'static [OutputFormat] = &[OutputFormat::Html, OutputFormat::Json, OutputFormat::Markdown, OutputFormat::Csv];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            OutputFormat::Html     => "html",
+            OutputFormat::Json     => "json",
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::Csv      => "csv",
+        }
+    }
+
+    pub fn get_renderer(&self) -> Box<dyn Renderer> {
+        match self {
+            OutputFormat::Html     => Box::new(HtmlRenderer::default()),
+            OutputFormat::Json     => Box::new(JsonRenderer),
+            OutputFormat::Markdown => Box::new(MarkdownRenderer),
+            OutputFormat::Csv      => Box::new(CsvRenderer),
+        }
+    }
+
+    pub fn get_extension(&self) -> &'static str {
+        self.get_renderer().get_extension()
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|format| format.get_name() == name)
+    }
+}
@@ -0,0 +1,129 @@
+use std::{collections::{BTreeMap, HashMap}, fmt::Debug, fs, path::Path};
+
+use anyhow::Context;
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{image_metadata::{Dimensions, ImageFormat, ImageHeader}, Img, ProjectDir};
+
+// Bumped whenever the cached facts change meaning, so that the caches of older versions are ignored instead of trusted.
+pub const SCAN_CACHE_VERSION: u32 = 1;
+
+// What was learned about every image of a previous scan, keyed by path. An entry is only used while the size
+// and the modification time of the file stay the same, so the changed files are read again.
+#[derive(Serialize, Deserialize)]
+pub struct ScanCache {
+    version: u32,
+    entries: HashMap<String, CachedImage>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct CachedImage {
+    size_bytes: u64,
+    // nanoseconds since the unix epoch
+    modified: Option<i64>,
+    format: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    content_hash: Option<String>,
+    // false when similar images were never searched for, since a missing perceptual hash may also mean a single color image
+    is_perceptually_hashed: bool,
+    perceptual_hash: Option<u64>,
+}
+
+impl Debug for ScanCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScanCache").field("version", &self.version).field("entries", &self.entries.len()).finish()
+    }
+}
+
+impl CachedImage {
+    fn get_header(&self) -> ImageHeader {
+        ImageHeader {
+            format: self.format.as_deref().and_then(ImageFormat::from_name),
+            dimensions: self.width.zip(self.height).map(|(width, height)| Dimensions { width, height }),
+        }
+    }
+
+    fn is_unchanged(&self, img: &Img) -> bool {
+        self.size_bytes == img.size_bytes && self.modified == get_modified_nanos(img)
+    }
+}
+
+impl ScanCache {
+    // An empty cache when the file doesn't exist, can't be parsed or was written by another version,
+    // since the worst that can happen then is a slower scan.
+    pub fn load(path: &str) -> Self {
+        fs::read(path).ok()
+            .and_then(|bytes| serde_json::from_slice::<ScanCache>(&bytes).ok())
+            .filter(|cache| cache.version == SCAN_CACHE_VERSION)
+            .unwrap_or_else(|| ScanCache { version: SCAN_CACHE_VERSION, entries: HashMap::new() })
+    }
+
+    // Writes the cache to a temporary file next to `path` and renames it, so that an interrupted write can't leave a broken cache.
+    pub fn save(&self, path: &str) -> anyhow::Result<()> {
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir).context(format!("Failed to create the cache directory `{}`", dir.display()).red())?;
+        }
+        let temp_path = format!("{}.tmp", path);
+        fs::write(&temp_path, serde_json::to_vec(self)?).context(format!("Failed to write the cache file `{}`", temp_path).red())?;
+        fs::rename(&temp_path, path).context(format!("Failed to replace the cache file `{}`", path).red())?;
+        Ok(())
+    }
+
+    // the header of the image, if it is in the cache and its file didn't change since
+    pub fn get_header(&self, img: &Img) -> Option<ImageHeader> {
+        self.get_entry(img).map(CachedImage::get_header)
+    }
+
+    // Fills the content hash of the image from the cache and, when the scan searches for similar images and it was
+    // computed before, its perceptual hash. Returns whether the perceptual hash was filled.
+    pub fn fill_hashes(&self, img: &mut Img, is_perceptually_hashing: bool) -> bool {
+        let Some(entry) = self.get_entry(img) else { return false };
+        img.content_hash = entry.content_hash.clone();
+        // the other scans don't show the perceptual hashes, the cache keeps them for the next one that does
+        let is_filled = is_perceptually_hashing && entry.is_perceptually_hashed;
+        if is_filled {
+            img.perceptual_hash = entry.perceptual_hash;
+        }
+        is_filled
+    }
+
+    // Replaces the entries under `dir_path` with the images of the projects, so that the deleted files are forgotten.
+    // The perceptual hashes of the unchanged files are kept, when they were not computed this time.
+    pub fn update(&mut self, dir_path: &str, project_dirs: &BTreeMap<String, ProjectDir>, is_perceptually_hashed: bool) {
+        let dir_prefix = format!("{}/", dir_path.trim_end_matches('/'));
+        let (old_entries, kept_entries): (HashMap<_, _>, HashMap<_, _>) = std::mem::take(&mut self.entries).into_iter()
+            .partition(|(path, _)| path.starts_with(&dir_prefix));
+        self.entries = kept_entries;
+
+        for img in project_dirs.values().flat_map(|project_dir| project_dir.images.iter()) {
+            let (is_perceptually_hashed, perceptual_hash) = if is_perceptually_hashed {
+                (true, img.perceptual_hash)
+            } else {
+                old_entries.get(&img.path)
+                    .filter(|entry| entry.is_unchanged(img))
+                    .map(|entry| (entry.is_perceptually_hashed, entry.perceptual_hash))
+                    .unwrap_or((false, None))
+            };
+            self.entries.insert(img.path.clone(), CachedImage {
+                size_bytes: img.size_bytes,
+                modified: get_modified_nanos(img),
+                format: img.header.format.map(|format| format.get_name().to_owned()),
+                width: img.header.dimensions.map(|dimensions| dimensions.width),
+                height: img.header.dimensions.map(|dimensions| dimensions.height),
+                content_hash: img.content_hash.clone(),
+                is_perceptually_hashed,
+                perceptual_hash,
+            });
+        }
+    }
+
+    fn get_entry(&self, img: &Img) -> Option<&CachedImage> {
+        self.entries.get(&img.path).filter(|entry| entry.is_unchanged(img))
+    }
+}
+
+fn get_modified_nanos(img: &Img) -> Option<i64> {
+    img.modified.and_then(|modified| modified.timestamp_nanos_opt())
+}
@@ -0,0 +1,382 @@
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, fs};
+
+use anyhow::{anyhow, Context};
+use colored::*;
+use serde::Deserialize;
+
+use crate::{json_export::JSON_SCHEMA_VERSION, ScanResult};
+
+// What is compared of a scan, either read from a json export or taken from a scan of the current tree
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    // where it came from, like the path of the json file
+    pub label: String,
+    pub generated_at: String,
+    pub root_dir: String,
+    // keyed by the path relative to the root directory, so that scans of different checkouts can be compared
+    pub images: BTreeMap<String, SnapshotImage>,
+    // icon set id -> class names
+    pub icon_sets: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotImage {
+    pub project_name: String,
+    // the absolute path at the time of the scan
+    pub path: String,
+    pub relative_path: String,
+    pub size_bytes: u64,
+    pub content_hash: Option<String>,
+    // the data uri of a small png of the image, kept by the json exports so that it can be shown after the file changed.
+    // None for the scans of the current tree, whose files can be shown as they are.
+    pub thumbnail: Option<String>,
+}
+
+impl SnapshotImage {
+    fn has_same_content(&self, other: &SnapshotImage) -> bool {
+        match (&self.content_hash, &other.content_hash) {
+            (Some(hash), Some(other_hash)) => hash == other_hash,
+            _ => self.size_bytes == other.size_bytes,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ImageChange {
+    Added(SnapshotImage),
+    Removed(SnapshotImage),
+    // same content at a new path
+    Renamed { old: SnapshotImage, new: SnapshotImage },
+    // same path with a different content
+    Modified { old: SnapshotImage, new: SnapshotImage },
+}
+
+impl ImageChange {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ImageChange::Added(_) => "added",
+            ImageChange::Removed(_) => "removed",
+            ImageChange::Renamed { .. } => "renamed",
+            ImageChange::Modified { .. } => "modified",
+        }
+    }
+
+    // the image as it is in the new snapshot, or as it was in the old one when it was removed
+    pub fn get_latest(&self) -> &SnapshotImage {
+        match self {
+            ImageChange::Added(img) | ImageChange::Removed(img) => img,
+            ImageChange::Renamed { new, .. } | ImageChange::Modified { new, .. } => new,
+        }
+    }
+}
+
+// The changes of a project, sorted by the relative path of the latest image
+#[derive(Debug, Clone)]
+pub struct ProjectDiff {
+    pub name: String,
+    pub changes: Vec<ImageChange>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IconSetDiff {
+    pub id: String,
+    pub added_class_names: Vec<String>,
+    pub removed_class_names: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanDiff {
+    pub old: Snapshot,
+    pub new: Snapshot,
+    // only the projects that changed, sorted by name
+    pub projects: Vec<ProjectDiff>,
+    // only the icon sets that changed, sorted by id. A set that exists in only one of the snapshots is all added or removed.
+    pub icon_sets: Vec<IconSetDiff>,
+}
+
+impl ScanDiff {
+    pub fn is_empty(&self) -> bool {
+        self.projects.is_empty() && self.icon_sets.is_empty()
+    }
+
+    pub fn count_changes(&self, name: &str) -> usize {
+        self.projects.iter().flat_map(|project_diff| &project_diff.changes).filter(|change| change.get_name() == name).count()
+    }
+}
+
+// the subset of the json export that the diff needs
+#[derive(Deserialize)]
+struct JsonSnapshot {
+    schema_version: u32,
+    generated_at: String,
+    root_dir: String,
+    projects: Vec<JsonSnapshotProject>,
+    #[serde(default)]
+    icon_sets: Vec<JsonSnapshotIconSet>,
+}
+
+#[derive(Deserialize)]
+struct JsonSnapshotProject {
+    name: String,
+    images: Vec<JsonSnapshotImage>,
+}
+
+#[derive(Deserialize)]
+struct JsonSnapshotImage {
+    path: String,
+    size_bytes: u64,
+    content_hash: Option<String>,
+    // missing in the exports of older versions
+    #[serde(default)]
+    thumbnail: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonSnapshotIconSet {
+    id: String,
+    class_names: Vec<String>,
+}
+
+impl Snapshot {
+    // Reads a file that was generated with `--format json`
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let json = fs::read_to_string(path).context(format!("Unable to read the scan `{}`", path).red())?;
+        let json_snapshot: JsonSnapshot = serde_json::from_str(&json)
+            .context(format!("`{}` is not a json export of img-dumper", path).red())?;
+        if json_snapshot.schema_version != JSON_SCHEMA_VERSION {
+            return Err(anyhow!(format!("`{}` has the schema version {}, only version {} can be compared",
+                path, json_snapshot.schema_version, JSON_SCHEMA_VERSION).red()));
+        }
+
+        let mut images = BTreeMap::new();
+        for project in json_snapshot.projects {
+            for img in project.images {
+                let relative_path = get_relative_path(&img.path, &json_snapshot.root_dir);
+                images.insert(relative_path.clone(), SnapshotImage {
+                    project_name: project.name.clone(),
+                    path: img.path,
+                    relative_path,
+                    size_bytes: img.size_bytes,
+                    content_hash: img.content_hash,
+                    thumbnail: img.thumbnail,
+                });
+            }
+        }
+
+        Ok(Self {
+            label: path.to_owned(),
+            generated_at: json_snapshot.generated_at,
+            root_dir: json_snapshot.root_dir,
+            images,
+            icon_sets: json_snapshot.icon_sets.into_iter()
+                .map(|icon_set| (icon_set.id, icon_set.class_names.into_iter().collect()))
+                .collect(),
+        })
+    }
+
+    pub fn from_scan_result(scan_result: &ScanResult, label: &str) -> Self {
+        let images = scan_result.projects.values()
+            .flat_map(|project_dir| project_dir.images.iter().map(move |img| (project_dir, img)))
+            .map(|(project_dir, img)| {
+                let relative_path = get_relative_path(&img.path, &scan_result.root_dir);
+                (relative_path.clone(), SnapshotImage {
+                    project_name: project_dir.name.clone(),
+                    path: img.path.clone(),
+                    relative_path,
+                    size_bytes: img.size_bytes,
+                    content_hash: img.content_hash.clone(),
+                    thumbnail: None,
+                })
+            })
+            .collect();
+
+        Self {
+            label: label.to_owned(),
+            generated_at: scan_result.scanned_at.to_rfc3339(),
+            root_dir: scan_result.root_dir.clone(),
+            images,
+            icon_sets: scan_result.icon_sets.iter()
+                .filter(|icon_set| !icon_set.class_names.is_empty())
+                .map(|icon_set| (icon_set.title.clone(), icon_set.class_names.iter().cloned().collect()))
+                .collect(),
+        }
+    }
+}
+
+// Compares the images by their path relative to the root directory. An image that disappeared from one path and
+// appeared at another with the same content is reported as renamed, even when it moved to another project.
+pub fn diff_snapshots(old: Snapshot, new: Snapshot) -> ScanDiff {
+    let mut changes = Vec::new();
+    let mut removed_by_hash: HashMap<&str, Vec<&SnapshotImage>> = HashMap::new();
+    let mut removed_without_hash = Vec::new();
+    for (relative_path, old_img) in &old.images {
+        match new.images.get(relative_path) {
+            Some(new_img) if !old_img.has_same_content(new_img) => {
+                changes.push(ImageChange::Modified { old: old_img.clone(), new: new_img.clone() });
+            }
+            Some(_) => {}
+            None => match &old_img.content_hash {
+                Some(hash) => removed_by_hash.entry(hash).or_default().push(old_img),
+                None => removed_without_hash.push(old_img),
+            },
+        }
+    }
+
+    for (relative_path, new_img) in &new.images {
+        if old.images.contains_key(relative_path) {
+            continue;
+        }
+        // the removed images are in path order, so the same one is picked on every run
+        let renamed_from = new_img.content_hash.as_deref()
+            .and_then(|hash| removed_by_hash.get_mut(hash))
+            .filter(|removed| !removed.is_empty())
+            .map(|removed| removed.remove(0));
+        changes.push(match renamed_from {
+            Some(old_img) => ImageChange::Renamed { old: old_img.clone(), new: new_img.clone() },
+            None => ImageChange::Added(new_img.clone()),
+        });
+    }
+
+    changes.extend(removed_by_hash.into_values().flatten().chain(removed_without_hash)
+        .map(|old_img| ImageChange::Removed(old_img.clone())));
+
+    let mut changes_by_project: BTreeMap<String, Vec<ImageChange>> = BTreeMap::new();
+    for change in changes {
+        changes_by_project.entry(change.get_latest().project_name.clone()).or_default().push(change);
+    }
+    let projects = changes_by_project.into_iter()
+        .map(|(name, mut changes)| {
+            changes.sort_by(|a, b| a.get_latest().relative_path.cmp(&b.get_latest().relative_path));
+            ProjectDiff { name, changes }
+        })
+        .collect();
+
+    let icon_set_ids: BTreeSet<&String> = old.icon_sets.keys().chain(new.icon_sets.keys()).collect();
+    let empty = BTreeSet::new();
+    let icon_sets = icon_set_ids.into_iter()
+        .map(|id| {
+            let old_class_names = old.icon_sets.get(id).unwrap_or(&empty);
+            let new_class_names = new.icon_sets.get(id).unwrap_or(&empty);
+            IconSetDiff {
+                id: id.clone(),
+                added_class_names: new_class_names.difference(old_class_names).cloned().collect(),
+                removed_class_names: old_class_names.difference(new_class_names).cloned().collect(),
+            }
+        })
+        .filter(|icon_set_diff| !icon_set_diff.added_class_names.is_empty() || !icon_set_diff.removed_class_names.is_empty())
+        .collect();
+
+    ScanDiff { old, new, projects, icon_sets }
+}
+
+// the path without the root directory, or the whole path when it is not inside it
+pub fn get_relative_path(path: &str, root_dir: &str) -> String {
+    let root_dir = root_dir.trim_end_matches('/');
+    path.strip_prefix(root_dir).and_then(|path| path.strip_prefix('/')).unwrap_or(path).to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the images are `(project name, path relative to the root directory, content hash)`
+    fn snapshot(images: &[(&str, &str, Option<&str>)], icon_class_names: &[&str]) -> Snapshot {
+        Snapshot {
+            label: "snapshot".to_owned(),
+            generated_at: String::new(),
+            root_dir: "/root".to_owned(),
+            images: images.iter()
+                .map(|&(project_name, relative_path, content_hash)| (relative_path.to_owned(), SnapshotImage {
+                    project_name: project_name.to_owned(),
+                    path: format!("/root/{}", relative_path),
+                    relative_path: relative_path.to_owned(),
+                    size_bytes: content_hash.map_or(0, |hash| hash.len() as u64),
+                    content_hash: content_hash.map(str::to_owned),
+                    thumbnail: None,
+                }))
+                .collect(),
+            icon_sets: BTreeMap::from([("fa".to_owned(), icon_class_names.iter().map(|&class_name| class_name.to_owned()).collect())]),
+        }
+    }
+
+    // `(project, kind, old path, new path)` of every change
+    fn describe(diff: &ScanDiff) -> Vec<(&str, &str, &str, &str)> {
+        diff.projects.iter()
+            .flat_map(|project_diff| project_diff.changes.iter().map(move |change| {
+                let (old, new) = match change {
+                    ImageChange::Added(img) => ("", img.relative_path.as_str()),
+                    ImageChange::Removed(img) => (img.relative_path.as_str(), ""),
+                    ImageChange::Renamed { old, new } | ImageChange::Modified { old, new } => (old.relative_path.as_str(), new.relative_path.as_str()),
+                };
+                (project_diff.name.as_str(), change.get_name(), old, new)
+            }))
+            .collect()
+    }
+
+    #[test]
+    fn every_kind_of_image_change_is_classified() {
+        let old = snapshot(&[
+            ("a", "a/same.png", Some("1")),
+            ("a", "a/edited.png", Some("2")),
+            ("a", "a/old-name.png", Some("3")),
+            ("a", "a/deleted.png", Some("4")),
+            ("a", "a/moved-and-edited.png", Some("5")),
+            ("a", "a/unreadable.png", None),
+            ("a", "a/to-other-project.png", Some("6")),
+        ], &[]);
+        let new = snapshot(&[
+            ("a", "a/same.png", Some("1")),
+            ("a", "a/edited.png", Some("22")),
+            ("a", "a/new-name.png", Some("3")),
+            ("a", "a/elsewhere/moved-and-edited.png", Some("55")),
+            ("a", "a/new.png", Some("7")),
+            ("b", "b/to-other-project.png", Some("6")),
+        ], &[]);
+
+        assert_eq!(describe(&diff_snapshots(old, new)), vec![
+            ("a", "removed", "a/deleted.png", ""),
+            ("a", "modified", "a/edited.png", "a/edited.png"),
+            // a rename with a new content can't be told apart from a deletion and an addition
+            ("a", "added", "", "a/elsewhere/moved-and-edited.png"),
+            ("a", "removed", "a/moved-and-edited.png", ""),
+            ("a", "renamed", "a/old-name.png", "a/new-name.png"),
+            ("a", "added", "", "a/new.png"),
+            ("a", "removed", "a/unreadable.png", ""),
+            // the change belongs to the project that the image is in now
+            ("b", "renamed", "a/to-other-project.png", "b/to-other-project.png"),
+        ]);
+    }
+
+    #[test]
+    fn copies_are_renamed_once_and_images_without_hash_compare_their_size() {
+        // two removed copies and a single new one: the first copy in path order is the renamed one
+        let old = snapshot(&[("a", "a/1.png", Some("x")), ("a", "a/2.png", Some("x")), ("a", "a/size.png", None)], &[]);
+        let new = snapshot(&[("a", "a/3.png", Some("x")), ("a", "a/size.png", Some("yy"))], &[]);
+        assert_eq!(describe(&diff_snapshots(old, new)), vec![
+            ("a", "removed", "a/2.png", ""),
+            ("a", "renamed", "a/1.png", "a/3.png"),
+            ("a", "modified", "a/size.png", "a/size.png"),
+        ]);
+
+        let old = snapshot(&[("a", "a/size.png", None)], &[]);
+        let new = snapshot(&[("a", "a/size.png", Some(""))], &[]);
+        assert!(diff_snapshots(old, new).is_empty());
+    }
+
+    #[test]
+    fn icon_classes_are_added_and_removed_per_set() {
+        let mut old = snapshot(&[], &["fa-bars", "fa-old"]);
+        old.icon_sets.insert("gone".to_owned(), BTreeSet::from(["gone-a".to_owned()]));
+        let new = snapshot(&[], &["fa-bars", "fa-new", "fa-newer"]);
+        let diff = diff_snapshots(old, new);
+
+        assert!(diff.projects.is_empty());
+        let icon_sets: Vec<(&str, &[String], &[String])> = diff.icon_sets.iter()
+            .map(|icon_set_diff| (icon_set_diff.id.as_str(), icon_set_diff.added_class_names.as_slice(), icon_set_diff.removed_class_names.as_slice()))
+            .collect();
+        assert_eq!(icon_sets, vec![
+            ("fa", &["fa-new".to_owned(), "fa-newer".to_owned()][..], &["fa-old".to_owned()][..]),
+            ("gone", &[][..], &["gone-a".to_owned()][..]),
+        ]);
+    }
+}
@@ -0,0 +1,432 @@
+use std::{collections::{BTreeMap, HashSet}, fs, path::{Path, PathBuf}, sync::RwLock};
+
+use chrono::{DateTime, Local};
+use rayon::prelude::*;
+use walkdir::WalkDir;
+
+use crate::{duplicates::{self, DuplicateGroup}, image_metadata::{read_image_header, ImageHeader},
+    references::{self, BrokenReference, SourceReference, SOURCE_EXTENSIONS}, scan_cache::ScanCache, similarity::{self, SimilarCluster}, special_files::{self, IconSet, ParsableFileSpec}};
+
+// extensions that are searched for, when none are given to the `Scanner`
+pub const DEFAULT_EXTENSIONS: &[&str] = &["svg", "png", "jpg", "jpeg", "gif", "bmp", "ico"];
+
+// names of folders that are ignored in each project, when none are given to the `Scanner`
+pub const DEFAULT_IGNORED_DIR_NAMES: &[&str] = &[
+    "bower_components",
+    "node_modules",
+    "vendor",
+    "dist",
+    "api",
+    "build_release",
+    "backup",
+    "temp"
+];
+
+#[derive(Debug, Default, Clone)]
+pub struct ProjectDir {
+    pub name: String,
+    pub path: String,
+    pub images: Vec<Img>,
+    // the source files that were searched for references to the images, empty unless references are searched for
+    pub source_files: Vec<String>,
+    // the references of the source files to images that don't exist, sorted by file and line
+    pub broken_references: Vec<BrokenReference>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Img {
+    pub name: String,
+    pub path: String,
+    pub extension: String,
+    // size of the file in bytes
+    pub size_bytes: u64,
+    // last modification time, None if the platform doesn't report it
+    pub modified: Option<DateTime<Local>>,
+    // the actual format and the pixel dimensions, read from the file header without decoding the image
+    pub header: ImageHeader,
+    // hex encoded blake3 hash of the content, None if the file couldn't be read
+    pub content_hash: Option<String>,
+    // difference hash of the decoded pixels, only computed when similar images are searched for
+    pub perceptual_hash: Option<u64>,
+    // the places in the source files of the project that point at the image, None unless references are searched for
+    pub references: Option<Vec<SourceReference>>,
+    // the image appeared since the previous snapshot of the history, always false without one
+    pub is_new: bool,
+}
+
+impl Img {
+    // true when the references were searched for and none was found
+    pub fn is_unused(&self) -> bool {
+        self.references.as_ref().is_some_and(|references| references.is_empty())
+    }
+}
+
+// Everything that a scan produced, ready to be handed to a consumer like the html or the json generator.
+#[derive(Debug)]
+pub struct ScanResult {
+    // the date and time when the scan was executed
+    pub scanned_at: DateTime<Local>,
+
+    // folder that contains the projects that were scanned
+    pub root_dir: String,
+
+    // extensions that were searched for, like svg, png, ...
+    pub extensions: Vec<String>,
+
+    // projects that contain at least one image or broken reference, keyed and ordered by name. Their images are sorted by name.
+    pub projects: BTreeMap<String, ProjectDir>,
+
+    // the icon font files that were found and parsed, in the order that their specs were given
+    pub icon_sets: Vec<IconSet>,
+
+    // groups of images with identical content, across all projects
+    pub duplicates: Vec<DuplicateGroup>,
+
+    // groups of images that look alike without being identical, empty unless similar images were searched for
+    pub similar_clusters: Vec<SimilarCluster>,
+
+    // when the previous snapshot of the history was taken, that the new images are compared to. None without one.
+    pub new_since: Option<DateTime<Local>>,
+}
+
+// Builder for a scan of a root directory, where every top level directory is considered a project.
+//
+//     let result = Scanner::new("/opt/lampp/htdocs")
+//         .extensions(["svg", "png"])
+//         .exclude("coverage")
+//         .scan()?;
+#[derive(Debug)]
+pub struct Scanner {
+    root_dir: String,
+    extensions: Vec<String>,
+    ignored_dir_names: Vec<String>,
+    special_file_specs: Vec<ParsableFileSpec>,
+    similarity_threshold: Option<u32>,
+    is_finding_references: bool,
+    // the path of the cache file and what it contained, with the images of the latest traversals
+    cache: Option<(String, RwLock<ScanCache>)>,
+}
+
+impl Scanner {
+    pub fn new(root_dir: impl Into<String>) -> Self {
+        Self {
+            root_dir: root_dir.into().replace('\\', "/"),
+            extensions: DEFAULT_EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+            ignored_dir_names: DEFAULT_IGNORED_DIR_NAMES.iter().map(|dir_name| dir_name.to_string()).collect(),
+            special_file_specs: Vec::new(),
+            similarity_threshold: None,
+            is_finding_references: false,
+            cache: None,
+        }
+    }
+
+    // replaces the extensions that are searched for, the leading dot is optional and they are matched case insensitively
+    pub fn extensions<S: Into<String>>(mut self, extensions: impl IntoIterator<Item = S>) -> Self {
+        self.extensions = normalize_list(extensions.into_iter()
+            .map(|ext| ext.into().trim().trim_start_matches('.').to_lowercase()));
+        self
+    }
+
+    // replaces the names of the folders that are ignored in each project
+    pub fn ignored_dir_names<S: Into<String>>(mut self, dir_names: impl IntoIterator<Item = S>) -> Self {
+        self.ignored_dir_names = normalize_list(dir_names.into_iter().map(Into::into));
+        self
+    }
+
+    // adds a folder name that is ignored in each project, on top of the current ones
+    pub fn exclude(mut self, dir_name: impl Into<String>) -> Self {
+        let mut dir_names = std::mem::take(&mut self.ignored_dir_names);
+        dir_names.push(dir_name.into());
+        self.ignored_dir_names = normalize_list(dir_names.into_iter());
+        self
+    }
+
+    // adds an icon font file that should be searched for and parsed after the projects are traversed
+    pub fn special_file(mut self, file_spec: ParsableFileSpec) -> Self {
+        self.special_file_specs.push(file_spec);
+        self
+    }
+
+    // searches for visually similar images, whose perceptual hashes differ in at most `threshold` of their 64 bits
+    pub fn find_similar(mut self, threshold: u32) -> Self {
+        self.similarity_threshold = Some(threshold);
+        self
+    }
+
+    // searches the source files of each project (html, php, js, css, ...) for the images that they point at,
+    // so that the images which nothing points at can be reported as unused
+    pub fn find_references(mut self) -> Self {
+        self.is_finding_references = true;
+        self
+    }
+
+    // Loads what the previous scans learned about the images from the file at `path`, so that only the images whose
+    // size or modification time changed are read and hashed again. The file is only written by `save_cache`.
+    pub fn cache_file(mut self, path: impl Into<String>) -> Self {
+        let path = path.into();
+        let cache = ScanCache::load(&path);
+        self.cache = Some((path, RwLock::new(cache)));
+        self
+    }
+
+    pub fn get_root_dir(&self) -> &str {
+        &self.root_dir
+    }
+
+    pub fn get_extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    pub fn get_ignored_dir_names(&self) -> &[String] {
+        &self.ignored_dir_names
+    }
+
+    pub fn get_special_file_specs(&self) -> &[ParsableFileSpec] {
+        &self.special_file_specs
+    }
+
+    pub fn get_similarity_threshold(&self) -> Option<u32> {
+        self.similarity_threshold
+    }
+
+    pub fn is_finding_references(&self) -> bool {
+        self.is_finding_references
+    }
+
+    pub fn get_cache_file(&self) -> Option<&str> {
+        self.cache.as_ref().map(|(path, _)| path.as_str())
+    }
+
+    // Writes the images of the latest traversals to the cache file, does nothing when no cache file was given.
+    pub fn save_cache(&self) -> anyhow::Result<()> {
+        match &self.cache {
+            Some((path, cache)) => cache.read().unwrap().save(path),
+            None => Ok(()),
+        }
+    }
+
+    // Runs every step of the scan. The steps are also exposed individually, for callers that want to report progress.
+    pub fn scan(&self) -> anyhow::Result<ScanResult> {
+        let projects = self.scan_projects();
+        let mut icon_sets = Vec::with_capacity(self.special_file_specs.len());
+        for file_spec in &self.special_file_specs {
+            if let Some(icon_set) = self.parse_special_file(file_spec, &projects)? {
+                icon_sets.push(icon_set);
+            }
+        }
+
+        Ok(self.make_result(projects, icon_sets))
+    }
+
+    pub fn make_result(&self, projects: BTreeMap<String, ProjectDir>, icon_sets: Vec<IconSet>) -> ScanResult {
+        ScanResult {
+            scanned_at: Local::now(),
+            root_dir: self.root_dir.clone(),
+            extensions: self.extensions.clone(),
+            duplicates: duplicates::find_duplicates(&projects),
+            similar_clusters: self.similarity_threshold
+                .map(|threshold| similarity::find_similar_clusters(&projects, threshold))
+                .unwrap_or_default(),
+            new_since: None,
+            projects,
+            icon_sets,
+        }
+    }
+
+    pub fn parse_special_file(&self, file_spec: &ParsableFileSpec, projects: &BTreeMap<String, ProjectDir>) -> anyhow::Result<Option<IconSet>> {
+        special_files::parse_special_file(file_spec, projects)
+    }
+
+    // Traverses the root directory and returns the projects that contain at least one image (or broken reference),
+    // with their images sorted by name and their contents hashed. The pixels are hashed too, when similar images are searched for.
+    pub fn scan_projects(&self) -> BTreeMap<String, ProjectDir> {
+        self.resolve_projects(self.traverse_projects())
+    }
+
+    // The part of `scan_projects` that is done for each project on its own. All the projects are kept, even the empty ones,
+    // so that `retraverse_project` can update a single one of them before they are given to `resolve_projects`.
+    pub fn traverse_projects(&self) -> BTreeMap<String, ProjectDir> {
+        let mut project_dirs = self.traverse_root_dir_and_make_project_map(None);
+        self.hash_projects(&mut project_dirs, &self.root_dir);
+        project_dirs
+    }
+
+    // Traverses a single project again, replacing it in the map or removing it, if its folder doesn't exist anymore.
+    pub fn retraverse_project(&self, project_dirs: &mut BTreeMap<String, ProjectDir>, project_name: &str) {
+        let mut retraversed = self.traverse_root_dir_and_make_project_map(Some(project_name));
+        self.hash_projects(&mut retraversed, &format!("{}/{}", self.root_dir.trim_end_matches('/'), project_name));
+        match retraversed.remove(project_name) {
+            Some(project_dir) => project_dirs.insert(project_name.to_owned(), project_dir),
+            None => project_dirs.remove(project_name),
+        };
+    }
+
+    // The part of `scan_projects` that needs all the projects: the references between them are resolved,
+    // and then the projects that have nothing to show are dropped.
+    pub fn resolve_projects(&self, mut project_dirs: BTreeMap<String, ProjectDir>) -> BTreeMap<String, ProjectDir> {
+        // before the projects without images are dropped, since their sources may point at the images of other projects
+        if self.is_finding_references {
+            references::find_references(&mut project_dirs, &self.root_dir, &self.extensions);
+        }
+        project_dirs.retain(|_, project_dir| !project_dir.images.is_empty() || !project_dir.broken_references.is_empty());
+
+        project_dirs
+    }
+
+    // Like `resolve_projects`, with the references that `read_source_references` returned for each project, by project name,
+    // so that the source files of the projects that didn't change are not read again.
+    pub fn resolve_projects_with_references(&self, mut project_dirs: BTreeMap<String, ProjectDir>,
+            source_references: &BTreeMap<String, Vec<SourceReference>>) -> BTreeMap<String, ProjectDir> {
+        if self.is_finding_references {
+            references::resolve_references(&mut project_dirs, source_references, &self.root_dir);
+        }
+        project_dirs.retain(|_, project_dir| !project_dir.images.is_empty() || !project_dir.broken_references.is_empty());
+
+        project_dirs
+    }
+
+    // the references written in the source files of the project, empty when the references are not searched for
+    pub fn read_source_references(&self, project_dir: &ProjectDir) -> Vec<SourceReference> {
+        if !self.is_finding_references {
+            return Vec::new();
+        }
+        references::read_project_references(project_dir, &self.extensions)
+    }
+
+    // true for the names of the files and folders that are never traversed: the hidden ones and the ignored folders
+    pub fn is_ignored_name(&self, name: &str) -> bool {
+        name.starts_with('.') || self.ignored_dir_names.iter().any(|dir_name| dir_name == name)
+    }
+
+    // `dir_path` is the folder that was traversed, the cached images inside it that were not found anymore are forgotten
+    fn hash_projects(&self, project_dirs: &mut BTreeMap<String, ProjectDir>, dir_path: &str) {
+        project_dirs.values_mut().for_each(|f| f.images.sort_by(|a, b| a.name.cmp(&b.name)));
+
+        let mut perceptually_hashed_paths = HashSet::new();
+        if let Some((_, cache)) = &self.cache {
+            let cache = cache.read().unwrap();
+            for img in project_dirs.values_mut().flat_map(|project_dir| project_dir.images.iter_mut()) {
+                if cache.fill_hashes(img, self.similarity_threshold.is_some()) {
+                    perceptually_hashed_paths.insert(img.path.clone());
+                }
+            }
+        }
+
+        duplicates::hash_images(project_dirs);
+        if self.similarity_threshold.is_some() {
+            similarity::hash_images_perceptually(project_dirs, |img| !perceptually_hashed_paths.contains(&img.path));
+        }
+
+        if let Some((_, cache)) = &self.cache {
+            cache.write().unwrap().update(dir_path, project_dirs, self.similarity_threshold.is_some());
+        }
+    }
+
+    // When `only_project` is given, the other top level folders are skipped.
+    // The projects don't depend on each other, so they are traversed in parallel and collected by name,
+    // which gives the same map as traversing them one after the other.
+    fn traverse_root_dir_and_make_project_map(&self, only_project: Option<&str>) -> BTreeMap<String, ProjectDir> {
+        let Ok(entries) = fs::read_dir(&self.root_dir) else { return BTreeMap::new() };
+        let project_paths: Vec<(String, PathBuf)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| Some((entry.file_name().to_str()?.to_owned(), entry.path())))
+            .filter(|(name, path)| !self.is_ignored_name(name) && path.is_dir() && only_project.is_none_or(|only| only == name))
+            .collect();
+
+        let cache = self.cache.as_ref().map(|(_, cache)| cache.read().unwrap());
+        project_paths.into_par_iter()
+            .map(|(name, path)| (name.clone(), self.traverse_project(name, &path, cache.as_deref())))
+            .collect()
+    }
+
+    fn traverse_project(&self, name: String, path: &Path, cache: Option<&ScanCache>) -> ProjectDir {
+        let mut project_dir = ProjectDir {
+            name,
+            path: path.to_string_lossy().replace("\\","/"),
+            images: Vec::new(),
+            source_files: Vec::new(),
+            broken_references: Vec::new(),
+        };
+
+        // sorted, so that images with the same name always end up in the same order
+        for entry in WalkDir::new(path)
+                .follow_links(true)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_entry(|e| e.depth() == 0 || e.file_name().to_str().is_some_and(|s| !self.is_ignored_name(s))) {
+            let entry = if let Ok(x) = entry { x } else { continue };
+            let entry_path = entry.path();
+            let extension = entry_path.extension().unwrap_or_default().to_str().unwrap_or_default().to_lowercase();
+
+            if self.extensions.contains(&extension) {
+                let name = entry_path.file_stem().unwrap_or_default().to_str().unwrap_or_default();
+                let metadata = entry.metadata().ok();
+                let mut img = Img {
+                    name: name.to_owned(),
+                    path: entry_path.to_string_lossy().replace("\\","/"),
+                    extension: extension.to_owned(),
+                    size_bytes: metadata.as_ref().map(|m| m.len()).unwrap_or_default(),
+                    modified: metadata.and_then(|m| m.modified().ok()).map(DateTime::<Local>::from),
+                    header: ImageHeader::default(),
+                    content_hash: None,
+                    perceptual_hash: None,
+                    references: None,
+                    is_new: false,
+                };
+                img.header = match cache.and_then(|cache| cache.get_header(&img)) {
+                    Some(header) => header,
+                    None => read_image_header(entry_path).unwrap_or_default(),
+                };
+                project_dir.images.push(img);
+            } else if self.is_finding_references && entry.file_type().is_file() && SOURCE_EXTENSIONS.contains(&extension.as_str()) {
+                project_dir.source_files.push(entry_path.to_string_lossy().replace("\\","/"));
+            }
+        }
+
+        project_dir
+    }
+}
+
+// trims the values, drops the empty ones and the duplicates, keeping the first occurrence
+pub fn normalize_list(values: impl Iterator<Item = String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for value in values {
+        let value = value.trim();
+        if !value.is_empty() && !normalized.iter().any(|v| v == value) {
+            normalized.push(value.to_owned());
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_perceptual_hash(scanner: &Scanner) -> Option<u64> {
+        let projects = scanner.scan_projects();
+        scanner.save_cache().unwrap();
+        projects["project"].images[0].perceptual_hash
+    }
+
+    #[test]
+    fn cached_perceptual_hashes_are_only_used_when_searching_for_similar_images() {
+        let root_dir = std::env::temp_dir().join(format!("img-dumper-scanner-{}-cache", std::process::id()))
+            .to_string_lossy().replace('\\', "/");
+        let _ = fs::remove_dir_all(&root_dir);
+        fs::create_dir_all(format!("{}/project", root_dir)).unwrap();
+        image::GrayImage::from_fn(16, 16, |x, y| image::Luma([(x * 16 + y) as u8]))
+            .save(format!("{}/project/gradient.png", root_dir)).unwrap();
+        let cache_path = format!("{}/cache.json", root_dir);
+
+        let similar_hash = get_perceptual_hash(&Scanner::new(&root_dir).find_similar(10).cache_file(&cache_path));
+        assert!(similar_hash.is_some());
+        assert_eq!(get_perceptual_hash(&Scanner::new(&root_dir).cache_file(&cache_path)), None);
+        // the plain scan didn't forget it
+        let cache = ScanCache::load(&cache_path);
+        let mut img = Scanner::new(&root_dir).scan_projects().remove("project").unwrap().images.remove(0);
+        assert!(cache.fill_hashes(&mut img, true));
+        assert_eq!(img.perceptual_hash, similar_hash);
+        fs::remove_dir_all(&root_dir).unwrap();
+    }
+}
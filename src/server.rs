@@ -0,0 +1,222 @@
+use std::{collections::BTreeMap, fs::File, io::Write, path::{Path, PathBuf}, sync::{mpsc, Arc, Mutex, RwLock},
+    thread, time::Duration};
+
+use anyhow::anyhow;
+use colored::*;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use img_dumper::{html_report::generate_html_page_as_string, json_export::{generate_icons_api_json, generate_images_api_json,
+    generate_projects_api_json, ImageQuery}, utils::get_mime_type, ProjectDir, ScanResult};
+
+use crate::{app_config::AppConfig, message_printer::statusln, open_in_the_browser, watcher};
+
+pub const DEFAULT_PORT: u16 = 8470;
+
+// a comment is sent to every open event stream this often, to find out which clients went away
+const EVENT_STREAM_KEEPALIVE: Duration = Duration::from_secs(15);
+
+// reloads the page when the server says that the report changed
+const LIVE_RELOAD_SCRIPT: &str = "<script>new EventSource('/events').addEventListener('reload', () => location.reload());</script>";
+
+// What the request handlers share with the watcher
+struct ServerState {
+    // canonical path of the root directory, nothing outside of it is served
+    root_dir: PathBuf,
+    port: u16,
+    scan_result: RwLock<ScanResult>,
+    report_html: RwLock<String>,
+    // one sender for every open event stream
+    reload_senders: Mutex<Vec<mpsc::Sender<()>>>,
+}
+
+impl ServerState {
+    fn update(&self, scan_result: ScanResult) -> anyhow::Result<()> {
+        let report_html = make_report_html(&scan_result)?;
+        *self.report_html.write().unwrap() = report_html;
+        *self.scan_result.write().unwrap() = scan_result;
+        self.reload_senders.lock().unwrap().retain(|sender| sender.send(()).is_ok());
+        Ok(())
+    }
+}
+
+// Serves the report and the files of the root directory on 127.0.0.1, and rescans whenever a project changes,
+// until the process is stopped.
+pub fn serve(app_config: &AppConfig, scan_result: ScanResult, traversed_projects: BTreeMap<String, ProjectDir>) -> anyhow::Result<()> {
+    let port = app_config.command_line_args.port.unwrap_or(DEFAULT_PORT);
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| anyhow!(format!("Unable to listen on port {}: {}", port, e).red()))?;
+    let root_dir = Path::new(app_config.scanner.get_root_dir()).canonicalize()
+        .map_err(|e| anyhow!(format!("Unable to resolve the root directory: {}", e).red()))?;
+
+    let state = Arc::new(ServerState {
+        root_dir,
+        port,
+        report_html: RwLock::new(make_report_html(&scan_result)?),
+        scan_result: RwLock::new(scan_result),
+        reload_senders: Mutex::new(Vec::new()),
+    });
+
+    let server_state = Arc::clone(&state);
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let state = Arc::clone(&server_state);
+            thread::spawn(move || handle_request(request, &state));
+        }
+    });
+
+    let url = format!("http://127.0.0.1:{}/", port);
+    statusln!("\nServing the report at {}", url);
+    open_in_the_browser(&url);
+
+    watcher::watch(app_config, traversed_projects, |scan_result| state.update(scan_result))
+}
+
+fn make_report_html(scan_result: &ScanResult) -> anyhow::Result<String> {
+    let html = generate_html_page_as_string(scan_result)?;
+    Ok(match html.strip_suffix("</html>") {
+        Some(html) => format!("{}{}</html>", html, LIVE_RELOAD_SCRIPT),
+        None => format!("{}{}", html, LIVE_RELOAD_SCRIPT),
+    })
+}
+
+fn handle_request(request: Request, state: &ServerState) {
+    if !is_local_host(&request, state.port) {
+        let _ = request.respond(Response::from_string("Forbidden").with_status_code(403));
+        return;
+    }
+    if !matches!(request.method(), Method::Get | Method::Head) {
+        let _ = request.respond(Response::from_string("Method Not Allowed").with_status_code(405));
+        return;
+    }
+
+    let url = request.url().split('#').next().unwrap_or("/").to_owned();
+    let (url_path, query) = url.split_once('?').unwrap_or((&url, ""));
+    // when the browser went away before it got the response, there is nobody to tell
+    let _ = match url_path {
+        "/" => {
+            let html = state.report_html.read().unwrap().clone();
+            request.respond(Response::from_string(html).with_header(content_type_header("text/html; charset=utf-8")))
+        }
+        "/events" => {
+            stream_reload_events(request, state);
+            Ok(())
+        }
+        _ if url_path.starts_with("/api/") => {
+            let (status_code, json) = handle_api_request(url_path, &parse_query(query), state);
+            request.respond(Response::from_string(json).with_status_code(status_code)
+                .with_header(content_type_header("application/json")))
+        }
+        _ => match resolve_file_path(url_path, &state.root_dir).and_then(|path| File::open(&path).ok().map(|file| (path, file))) {
+            Some((path, file)) => {
+                let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default().to_lowercase();
+                request.respond(Response::from_file(file).with_header(content_type_header(get_mime_type(&extension))))
+            }
+            None => request.respond(Response::from_string("Not Found").with_status_code(404)),
+        },
+    };
+}
+
+// Answers the json query api with a status code and a body:
+// `/api/projects`, `/api/images?q=logo&ext=svg,png&project=shop` and `/api/icons?set=font-awesome`.
+// There are no CORS headers on purpose, so that other sites can't read the inventory from the browser.
+fn handle_api_request(url_path: &str, query: &[(String, String)], state: &ServerState) -> (u16, String) {
+    fn get_values<'a>(query: &'a [(String, String)], key: &'a str) -> impl Iterator<Item = &'a str> {
+        query.iter().filter(move |(k, _)| k == key).map(|(_, value)| value.as_str())
+    }
+    let get_value = |key| get_values(query, key).find(|value| !value.is_empty());
+
+    let scan_result = state.scan_result.read().unwrap();
+    let json = match url_path {
+        "/api/projects" => generate_projects_api_json(&scan_result).map(Some),
+        "/api/images" => {
+            let image_query = ImageQuery {
+                text: get_value("q").map(str::to_owned),
+                // both `ext=svg,png` and `ext=svg&ext=png`
+                extensions: get_values(query, "ext")
+                    .flat_map(|value| value.split(','))
+                    .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+                    .filter(|extension| !extension.is_empty())
+                    .collect(),
+                project: get_value("project").map(str::to_owned),
+            };
+            generate_images_api_json(&scan_result, &image_query).map(Some)
+        }
+        "/api/icons" => generate_icons_api_json(&scan_result, get_value("set")),
+        _ => Ok(None),
+    };
+
+    match json {
+        Ok(Some(json)) => (200, json),
+        Ok(None) => {
+            let message = match get_value("set") {
+                Some(set_id) if url_path == "/api/icons" => format!("No icon set with the id `{}` was parsed", set_id),
+                _ => format!("Unknown endpoint {}", url_path),
+            };
+            (404, make_api_error(&message))
+        }
+        Err(e) => (500, make_api_error(&e.to_string())),
+    }
+}
+
+fn make_api_error(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+// The decoded key value pairs of a query string, where `+` stands for a space
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    let decode = |part: &str| percent_encoding::percent_decode_str(&part.replace('+', " ")).decode_utf8_lossy().into_owned();
+    query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+// Only requests that were addressed to the loopback interface by name are answered,
+// so that other sites can't read the files through DNS rebinding.
+fn is_local_host(request: &Request, port: u16) -> bool {
+    let Some(host) = request.headers().iter().find(|header| header.field.equiv("Host")) else { return false };
+    let host = host.value.as_str();
+    ["127.0.0.1", "localhost", "[::1]"].iter().any(|name| host == *name || host == format!("{}:{}", name, port))
+}
+
+// The file that the url path points at, if it is a file inside the root directory
+fn resolve_file_path(url_path: &str, root_dir: &Path) -> Option<PathBuf> {
+    let decoded = percent_encoding::percent_decode_str(url_path).decode_utf8().ok()?;
+    // `/C:/dir/file.png` on windows
+    let path = match decoded.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => &decoded[1..],
+        _ => &decoded[..],
+    };
+    let canonical_path = Path::new(path).canonicalize().ok()?;
+    (canonical_path.starts_with(root_dir) && canonical_path.is_file()).then_some(canonical_path)
+}
+
+// Keeps the connection open and sends a `reload` event after every rescan, until the browser goes away.
+fn stream_reload_events(request: Request, state: &ServerState) {
+    let (sender, receiver) = mpsc::channel();
+    state.reload_senders.lock().unwrap().push(sender);
+
+    // the response is written by hand, since the body of a normal response is buffered until it ends
+    let mut writer = request.into_writer();
+    let mut send = |message: &str| writer.write_all(message.as_bytes()).and_then(|_| writer.flush()).is_ok();
+    if !send("HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n") {
+        return;
+    }
+    loop {
+        let message = match receiver.recv_timeout(EVENT_STREAM_KEEPALIVE) {
+            Ok(()) => "event: reload\ndata: reload\n\n",
+            Err(mpsc::RecvTimeoutError::Timeout) => ": keepalive\n\n",
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        };
+        if !send(message) {
+            return;
+        }
+    }
+}
+
+fn content_type_header(mime_type: &str) -> Header {
+    Header::from_bytes(&b"Content-Type"[..], mime_type.as_bytes()).unwrap()
+}
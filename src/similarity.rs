@@ -0,0 +1,222 @@
+use std::{collections::{BTreeMap, HashMap}, path::Path};
+
+use image::{imageops::{self, FilterType}, GrayImage, ImageBuffer, Luma, RgbaImage};
+use rayon::prelude::*;
+use resvg::{tiny_skia, usvg};
+
+use crate::{image_metadata::ImageFormat, Img, ProjectDir};
+
+// Maximum number of differing bits (out of 64) for two images to be considered visually similar
+pub const DEFAULT_SIMILARITY_THRESHOLD: u32 = 10;
+
+// The size that svg files are rasterized to, before they are hashed like every other image
+const SVG_RASTER_SIZE: u32 = 64;
+
+// One of the visually similar images
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SimilarImage {
+    pub project_name: String,
+    pub path: String,
+    pub name: String,
+}
+
+// Images that look alike, like the same icon exported in different sizes or with a different compression.
+// Groups where every image has the same content are left out, since they are already reported as duplicates.
+#[derive(Debug, Clone)]
+pub struct SimilarCluster {
+    // sorted by project name and path, there are always at least two
+    pub images: Vec<SimilarImage>,
+    // the biggest distance between the hash of any image and the hash of the first one
+    pub max_distance: u32,
+}
+
+// Computes the difference hash (dHash) of the image: it is scaled down to 9x8 gray pixels and every bit tells
+// whether a pixel is brighter than its right neighbour. Transparent areas are treated as white.
+// Images of a single color have no hash, since all of them would be "similar" to each other.
+pub fn compute_perceptual_hash(path: &Path, format: ImageFormat) -> Option<u64> {
+    let rgba = match format {
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::Bmp => image::open(path).ok()?.to_rgba8(),
+        ImageFormat::Svg => rasterize_svg(path)?,
+        ImageFormat::Ico => return None,
+    };
+    if rgba.width() == 0 || rgba.height() == 0 {
+        return None;
+    }
+
+    compute_difference_hash(&rgba)
+}
+
+fn compute_difference_hash(rgba: &RgbaImage) -> Option<u64> {
+    let gray = flatten_on_white(rgba);
+    if gray.pixels().all(|pixel| pixel[0] == gray.get_pixel(0, 0)[0]) {
+        return None;
+    }
+
+    let small = imageops::resize(&gray, 9, 8, FilterType::Triangle);
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+
+    Some(hash)
+}
+
+fn flatten_on_white(rgba: &RgbaImage) -> GrayImage {
+    ImageBuffer::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let alpha = a as f32 / 255.0;
+        let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+        Luma([(luma * alpha + 255.0 * (1.0 - alpha)).round() as u8])
+    })
+}
+
+pub(crate) fn rasterize_svg(path: &Path) -> Option<RgbaImage> {
+    let data = std::fs::read(path).ok()?;
+    let tree = usvg::Tree::from_data(&data, &usvg::Options::default()).ok()?;
+    let size = tree.size();
+    let scale = SVG_RASTER_SIZE as f32 / size.width().max(size.height());
+    let width = ((size.width() * scale).round() as u32).max(1);
+    let height = ((size.height() * scale).round() as u32).max(1);
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    // tiny-skia keeps the colors premultiplied by alpha
+    let mut rgba = RgbaImage::from_raw(width, height, pixmap.take())?;
+    for pixel in rgba.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        if a > 0 && a < 255 {
+            let unpremultiply = |c: u8| ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+            pixel.0 = [unpremultiply(r), unpremultiply(g), unpremultiply(b), a];
+        }
+    }
+
+    Some(rgba)
+}
+
+// Computes the perceptual hash of every image whose format is supported and that `needs_hash` accepts, in parallel
+pub fn hash_images_perceptually(projects: &mut BTreeMap<String, ProjectDir>, needs_hash: impl Fn(&Img) -> bool) {
+    let mut images: Vec<_> = projects.values_mut()
+        .flat_map(|project_dir| project_dir.images.iter_mut())
+        .filter(|img| needs_hash(img))
+        .collect();
+    images.par_iter_mut().for_each(|img| {
+        img.perceptual_hash = img.header.format.and_then(|format| compute_perceptual_hash(Path::new(&img.path), format));
+    });
+}
+
+// Clusters the images whose hashes differ in at most `threshold` bits, directly or through other images of the cluster.
+pub fn find_similar_clusters(projects: &BTreeMap<String, ProjectDir>, threshold: u32) -> Vec<SimilarCluster> {
+    let hashed: Vec<(&str, &Img, u64)> = projects.values()
+        .flat_map(|project_dir| project_dir.images.iter().map(move |img| (project_dir.name.as_str(), img)))
+        .filter_map(|(project_name, img)| img.perceptual_hash.map(|hash| (project_name, img, hash)))
+        .collect();
+
+    let mut parents: Vec<usize> = (0..hashed.len()).collect();
+    fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    for i in 0..hashed.len() {
+        for j in (i + 1)..hashed.len() {
+            if (hashed[i].2 ^ hashed[j].2).count_ones() <= threshold {
+                let (root_i, root_j) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                if root_i != root_j {
+                    parents[root_j] = root_i;
+                }
+            }
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..hashed.len() {
+        let root = find_root(&mut parents, i);
+        members.entry(root).or_default().push(i);
+    }
+
+    let mut clusters: Vec<SimilarCluster> = members.into_values()
+        .filter(|indexes| indexes.len() > 1)
+        // only byte-identical copies, those are already reported as duplicates
+        .filter(|indexes| indexes.iter().any(|&i| hashed[i].1.content_hash.is_none() || hashed[i].1.content_hash != hashed[indexes[0]].1.content_hash))
+        .map(|mut indexes| {
+            indexes.sort_by(|&a, &b| (hashed[a].0, &hashed[a].1.path).cmp(&(hashed[b].0, &hashed[b].1.path)));
+            let first_hash = hashed[indexes[0]].2;
+            SimilarCluster {
+                max_distance: indexes.iter().map(|&i| (hashed[i].2 ^ first_hash).count_ones()).max().unwrap_or(0),
+                images: indexes.iter()
+                    .map(|&i| SimilarImage {
+                        project_name: hashed[i].0.to_owned(),
+                        path: hashed[i].1.path.clone(),
+                        name: hashed[i].1.name.clone(),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    clusters.sort_by(|a, b| b.images.len().cmp(&a.images.len()).then_with(|| a.images.cmp(&b.images)));
+
+    clusters
+}
+
+pub fn format_perceptual_hash(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an image of the project `p` whose hash has the `bit_count` lowest bits set
+    fn image(name: &str, bit_count: u32, content_hash: &str) -> Img {
+        Img {
+            name: name.to_owned(),
+            path: format!("/p/{}.png", name),
+            content_hash: Some(content_hash.to_owned()),
+            perceptual_hash: Some(u64::MAX.checked_shr(64 - bit_count).unwrap_or(0)),
+            ..Img::default()
+        }
+    }
+
+    fn clusters_of(images: Vec<Img>, threshold: u32) -> Vec<Vec<String>> {
+        let project_dir = ProjectDir { name: "p".to_owned(), images, ..ProjectDir::default() };
+        let projects = BTreeMap::from([("p".to_owned(), project_dir)]);
+        find_similar_clusters(&projects, threshold).into_iter()
+            .map(|cluster| cluster.images.into_iter().map(|image| image.name).collect())
+            .collect()
+    }
+
+    #[test]
+    fn hashes_at_the_threshold_are_similar_and_just_over_it_are_not() {
+        let images = vec![image("a", 0, "1"), image("b", 10, "2")];
+        assert_eq!(clusters_of(images.clone(), 10), vec![vec!["a", "b"]]);
+        assert!(clusters_of(images, 9).is_empty());
+
+        let images = vec![image("a", 0, "1"), image("c", 11, "3")];
+        assert!(clusters_of(images, 10).is_empty());
+    }
+
+    #[test]
+    fn clusters_are_transitive_and_skip_identical_copies() {
+        // `c` is 20 bits away from `a`, but 10 from `b`
+        let images = vec![image("a", 0, "1"), image("b", 10, "2"), image("c", 20, "3"), image("far", 40, "4")];
+        let project_dir = ProjectDir { name: "p".to_owned(), images, ..ProjectDir::default() };
+        let clusters = find_similar_clusters(&BTreeMap::from([("p".to_owned(), project_dir)]), 10);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].images.iter().map(|image| image.name.as_str()).collect::<Vec<_>>(), vec!["a", "b", "c"]);
+        assert_eq!(clusters[0].max_distance, 20);
+
+        // the same file twice is a duplicate, not a similar image
+        assert!(clusters_of(vec![image("a", 3, "1"), image("copy", 3, "1")], 10).is_empty());
+        assert_eq!(clusters_of(vec![image("a", 3, "1"), image("copy", 3, "1"), image("b", 5, "2")], 10), vec![vec!["a", "b", "copy"]]);
+    }
+}
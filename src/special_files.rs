@@ -0,0 +1,333 @@
+use std::{collections::{BTreeMap, HashSet}, fmt::Debug, fs::File, io::{BufReader, Read}, path::{Path, PathBuf}};
+
+use anyhow::Context;
+use colored::*;
+
+use percent_encoding::percent_decode_str;
+
+use crate::{css_parser::{is_relative_url, parse_style_rules, rewrite_urls, split_url_suffix, StyleRule, Token}, html_escape::encode_url_path,
+    scanner::ProjectDir, utils::{join_paths, normalize_path}};
+
+// Receives the spec, the directory that the file was found in and a reader of the file.
+pub type ParserFn = fn(&ParsableFileSpec, &str, BufReader<File>) -> anyhow::Result<ParsedStylesheet>;
+
+// The style families of font awesome: their class since version 6, and their class of version 5 that 6 still declares
+const FONT_AWESOME_FAMILIES: &[(&str, &str)] = &[
+    ("fa-solid", "fas"), ("fa-regular", "far"), ("fa-light", "fal"), ("fa-thin", "fat"), ("fa-duotone", "fad"), ("fa-brands", "fab"),
+];
+
+// Describes an icon font css file, that may exist in a known absolute place or inside any project.
+pub struct ParsableFileSpec {
+    // a descriptive title for the file
+    pub title: String,
+    // the file name without extension
+    pub name: String,
+    pub extension: String,
+    // None when the file can only be inside a project
+    pub known_abs_dir: Option<String>,
+    pub known_abs_path: Option<String>,
+    // relative paths are relative to a *project* (top level folder inside root dir)
+    // if they are None, it means that the file can only be present in one place (known_abs_path)
+    pub relative_dir: Option<String>,
+    pub relative_path: Option<String>,
+    // the class that every icon of the set needs, like `fa`
+    pub base_class: String,
+    // the prefix of the icon class names, like `fa-`
+    pub class_prefix: String,
+    pub approximate_size_bytes: usize,
+    pub parser_fn: ParserFn,
+}
+
+// An icon font file that was found and parsed
+#[derive(Debug, Clone)]
+pub struct IconSet {
+    pub title: String,
+    // the directory that the file was found in
+    pub source_dir: String,
+    pub base_class: String,
+    pub class_prefix: String,
+    // every class name of the glyphs, sorted
+    pub class_names: Vec<String>,
+    // sorted by their first class name
+    pub glyphs: Vec<Glyph>,
+    // the classes of the styles that the file declares, like `fa-solid` and `fa-brands`, empty when it has a single style
+    pub style_families: Vec<String>,
+    // the parsed file, with its relative urls converted to absolute ones
+    pub css: String,
+    // the files that the css points at but that don't exist, like fonts that were not copied along with it
+    pub missing_files: Vec<String>,
+}
+
+// An icon of a set, with every class name that draws it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Glyph {
+    // in the order of the file, the first one is the name of the icon and the others are its aliases
+    pub class_names: Vec<String>,
+    // the character that the classes draw, None when their content is not a single character (like a ligature)
+    pub codepoint: Option<u32>,
+    // the class of the style family that the glyph is drawn with, None for the base class of the set
+    pub family: Option<String>,
+}
+
+impl Glyph {
+    // like `f0c9`, the way that icon fonts write their codepoints
+    pub fn get_codepoint_hex(&self) -> Option<String> {
+        self.codepoint.map(|codepoint| format!("{:04x}", codepoint))
+    }
+}
+
+// What a parser found in an icon font file
+#[derive(Debug, Clone, Default)]
+pub struct ParsedStylesheet {
+    // the classes that share a codepoint and a family are aliases of the same glyph
+    pub glyphs: Vec<Glyph>,
+    // the content that should be embedded in the report
+    pub css: String,
+    pub missing_files: Vec<String>,
+    // the class that every icon needs according to the file, which replaces the one of the spec
+    pub base_class: Option<String>,
+    pub style_families: Vec<String>,
+}
+
+impl Debug for ParsableFileSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParsableFileSpec").field("title", &self.title).field("name", &self.name).field("extension", &self.extension).field("known_abs_dir", &self.known_abs_dir).field("known_abs_path", &self.known_abs_path).field("relative_dir", &self.relative_dir).field("relative_path", &self.relative_path).field("base_class", &self.base_class).field("class_prefix", &self.class_prefix).field("approximate_size_bytes", &self.approximate_size_bytes).finish()
+    }
+}
+
+impl ParsableFileSpec {
+    // The base class defaults to the title and the class prefix to the title followed by a dash, see `html_classes`.
+    pub fn new(title: &str, name: &str, extension: &str, known_abs_dir: Option<String>, relative_dir: Option<String>, approximate_size_bytes: usize,
+            parser_fn: ParserFn) -> Self {
+        let known_abs_path = known_abs_dir.as_ref().map(|dir| format!("{}/{}.{}", dir, name, extension));
+        let relative_path = relative_dir.as_ref().map(|dir| format!("{}/{}.{}", dir, name, extension));
+        Self {
+            title: title.to_owned(),
+            name: name.to_owned(),
+            extension: extension.to_owned(),
+            relative_dir,
+            relative_path,
+            known_abs_dir,
+            known_abs_path,
+            base_class: title.to_owned(),
+            class_prefix: format!("{}-", title),
+            approximate_size_bytes,
+            parser_fn
+        }
+    }
+
+    pub fn html_classes(mut self, base_class: &str, class_prefix: &str) -> Self {
+        self.base_class = base_class.to_owned();
+        self.class_prefix = class_prefix.to_owned();
+        self
+    }
+}
+
+pub fn parse_special_file(file_spec: &ParsableFileSpec, projects_map: &BTreeMap<String, ProjectDir>)
+-> anyhow::Result<Option<IconSet>> {
+    let mut found_file_path = file_spec.known_abs_path.clone().unwrap_or_default();
+    let mut found_file_dir = file_spec.known_abs_dir.clone().unwrap_or_default();
+    let mut has_found_valid_path = true;
+    if !file_spec.known_abs_path.as_ref().is_some_and(|path| PathBuf::from(path).exists()) {
+        has_found_valid_path = false;
+        if let Some(relative_dir) = &file_spec.relative_dir {
+            for project in projects_map.values() {
+                let file_dir_str = format!("{}{}", project.path, relative_dir);
+                let file_path_str = format!("{}/{}.{}", file_dir_str, file_spec.name, file_spec.extension);
+                if PathBuf::from(&file_path_str).exists() {
+                    found_file_dir = file_dir_str;
+                    found_file_path = file_path_str;
+                    has_found_valid_path = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    if !has_found_valid_path {
+        return Ok(None);
+    }
+
+    let reader = BufReader::new(File::open(&found_file_path).context(
+        format!("specified file path `{}` for {} is not valid", found_file_path, &file_spec.title).red())?);
+
+    let mut parsed = (file_spec.parser_fn)(file_spec, &found_file_dir, reader)?;
+    parsed.glyphs.sort_by(|a, b| a.class_names.cmp(&b.class_names));
+    let mut class_names: Vec<String> = parsed.glyphs.iter().flat_map(|glyph| glyph.class_names.iter().cloned()).collect();
+    class_names.sort();
+
+    Ok(Some(IconSet {
+        title: file_spec.title.clone(),
+        source_dir: found_file_dir,
+        base_class: parsed.base_class.unwrap_or_else(|| file_spec.base_class.clone()),
+        class_prefix: file_spec.class_prefix.clone(),
+        class_names,
+        glyphs: parsed.glyphs,
+        style_families: parsed.style_families,
+        css: parsed.css,
+        missing_files: parsed.missing_files,
+    }))
+}
+
+// Finds the icon classes of the stylesheet, wherever their rules are in the file:
+// - the rules that set the `content` of a single class followed by `:before` or `::before`, alone or in a group
+//   like `.fa-bars:before, .fa-navicon:before {`, which is how font awesome 4 and 5, bootstrap icons and most icon fonts do it
+// - the rules that set the `--fa` custom property of a single class, like `.fa-bars { --fa: "\f0c9"; }` since font awesome 6.5
+// The style families of font awesome are found as well. Since version 6 the icons of a family that has its own font,
+// like `fa-brands`, follow the rule that sets the `font-weight` of the family. Version 5 lists them all together,
+// so they are all drawn with the solid family.
+pub fn parse_css_file(file_spec: &ParsableFileSpec, selected_abs_dir: &str, mut reader: BufReader<File>) -> anyhow::Result<ParsedStylesheet> {
+    let mut css = String::with_capacity(file_spec.approximate_size_bytes);
+    reader.read_to_string(&mut css).context(format!("Failed to read the file of {}", file_spec.title).red())?;
+    let rules = parse_style_rules(&css);
+
+    let style_families = get_font_awesome_families(&rules);
+    // class name -> codepoint and family, in the order of the file. A class that has more than one rule keeps the last one.
+    let mut icons: Vec<(String, Option<u32>, Option<&str>)> = Vec::new();
+    let mut section_family = None;
+    for rule in &rules {
+        if let Some(family) = get_section_family(rule, &style_families) {
+            section_family = Some(family);
+            continue;
+        }
+        for (class_name, codepoint) in get_icons(rule) {
+            match icons.iter_mut().find(|(name, _, _)| *name == class_name) {
+                Some(icon) => *icon = (class_name, codepoint, section_family),
+                None => icons.push((class_name, codepoint, section_family)),
+            }
+        }
+    }
+
+    let mut class_names: Vec<String> = icons.iter().map(|(class_name, _, _)| class_name.clone()).collect();
+    class_names.sort();
+    let base_class = style_families.iter()
+        .find(|family| *family == "fa-solid" || *family == "fas")
+        .or(style_families.first())
+        .cloned()
+        .or_else(|| get_declared_base_class(&rules, &class_names));
+
+    let mut glyphs: Vec<Glyph> = Vec::new();
+    for (class_name, codepoint, family) in icons {
+        // the icons of the base family need no other class
+        let family = family.filter(|family| Some(*family) != base_class.as_deref()).map(|family| family.to_owned());
+        let alias_of = codepoint.and_then(|_| glyphs.iter_mut().find(|glyph| glyph.codepoint == codepoint && glyph.family == family));
+        match alias_of {
+            Some(glyph) => glyph.class_names.push(class_name),
+            None => glyphs.push(Glyph { class_names: vec![class_name], codepoint, family }),
+        }
+    }
+
+    let (css, missing_files) = make_urls_absolute(&css, selected_abs_dir);
+    Ok(ParsedStylesheet {
+        glyphs,
+        css,
+        missing_files,
+        base_class,
+        style_families,
+    })
+}
+
+// the class names that the rule draws an icon for, with the codepoint of the icon
+fn get_icons(rule: &StyleRule) -> Vec<(String, Option<u32>)> {
+    if let Some(content) = get_string_declaration(rule, "content") {
+        let codepoint = get_codepoint(content);
+        rule.selectors.iter().filter_map(|selector| get_before_class_name(selector)).map(|class_name| (class_name, codepoint)).collect()
+    } else if let Some(content) = get_string_declaration(rule, "--fa") {
+        let codepoint = get_codepoint(content);
+        rule.selectors.iter().filter_map(|selector| get_class_name(selector)).map(|class_name| (class_name, codepoint)).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+// the codepoint of a single character string, like the `"\f0c9"` of a `content`
+fn get_codepoint(content: &str) -> Option<u32> {
+    let mut chars = content.chars();
+    chars.next().filter(|_| chars.next().is_none()).map(|c| c as u32)
+}
+
+// the value of the property, when the rule sets it to a non empty string
+fn get_string_declaration<'a>(rule: &'a StyleRule, name: &str) -> Option<&'a str> {
+    rule.declarations.iter()
+        .filter(|declaration| declaration.name == name)
+        .flat_map(|declaration| declaration.value.iter())
+        .find_map(|token| match token {
+            Token::String(value) if !value.is_empty() => Some(value.as_str()),
+            _ => None,
+        })
+}
+
+// the families that the stylesheet has a rule for, by the class of version 6 when it is declared
+fn get_font_awesome_families(rules: &[StyleRule]) -> Vec<String> {
+    let class_names: HashSet<String> = rules.iter()
+        .flat_map(|rule| rule.selectors.iter().filter_map(|selector| get_class_name(selector)))
+        .collect();
+    FONT_AWESOME_FAMILIES.iter()
+        .filter_map(|(class_name, short_class_name)| [class_name, short_class_name].into_iter().find(|name| class_names.contains(**name)))
+        .map(|class_name| class_name.to_string())
+        .collect()
+}
+
+// The family whose icons follow the rule, for a rule like `.fa-brands, .fab { font-weight: 400; }`
+fn get_section_family<'a>(rule: &StyleRule, style_families: &'a [String]) -> Option<&'a str> {
+    if !rule.declarations.iter().any(|declaration| declaration.name == "font-weight") {
+        return None;
+    }
+    let mut families = rule.selectors.iter().map(|selector| {
+        let class_name = get_class_name(selector)?;
+        let (family, short_family) = FONT_AWESOME_FAMILIES.iter().find(|(family, short_family)| class_name == *family || class_name == *short_family)?;
+        style_families.iter().find(|name| *name == family || *name == short_family).map(|name| name.as_str())
+    });
+    let first = families.next()??;
+    families.all(|family| family == Some(first)).then_some(first)
+}
+
+// The class that sets the font of the icons, like `bi` for `.bi::before, [class^="bi-"]::before { font-family: bootstrap-icons; }`
+fn get_declared_base_class(rules: &[StyleRule], class_names: &[String]) -> Option<String> {
+    rules.iter()
+        .filter(|rule| rule.declarations.iter().any(|declaration| declaration.name == "font-family"))
+        .flat_map(|rule| rule.selectors.iter().filter_map(|selector| get_before_class_name(selector)))
+        .find(|class_name| class_names.binary_search(class_name).is_err())
+}
+
+// `fa-bars` for the selector `.fa-bars`, None for any other selector
+fn get_class_name(selector: &[Token]) -> Option<String> {
+    match selector {
+        [Token::Delim('.'), Token::Ident(class_name)] => Some(class_name.clone()),
+        _ => None,
+    }
+}
+
+// `fa-bars` for the selector `.fa-bars:before` or `.fa-bars::before`, None for any other selector
+fn get_before_class_name(selector: &[Token]) -> Option<String> {
+    let pseudo_element = match selector {
+        [Token::Delim('.'), Token::Ident(_), Token::Colon, Token::Colon, pseudo_element] => pseudo_element,
+        [Token::Delim('.'), Token::Ident(_), Token::Colon, pseudo_element] => pseudo_element,
+        _ => return None,
+    };
+    match (&selector[1], pseudo_element) {
+        (Token::Ident(class_name), Token::Ident(name)) if name.eq_ignore_ascii_case("before") => Some(class_name.clone()),
+        _ => None,
+    }
+}
+
+// The relative urls of the stylesheet are relative to its directory, which the report is not in. They become percent-encoded
+// absolute paths that keep their query and fragment, like `/fonts/fa.eot?v=4.7.0#iefix` for `url('../fonts/fa.eot?v=4.7.0#iefix')`
+// in `/css/`. The other urls stay as they are. Also returns the files that the relative urls point at but that don't exist.
+fn make_urls_absolute(css: &str, selected_abs_dir: &str) -> (String, Vec<String>) {
+    let mut missing_files: Vec<String> = Vec::new();
+    let css = rewrite_urls(css, |url| {
+        if !is_relative_url(url) {
+            return None;
+        }
+        let (relative_path, suffix) = split_url_suffix(url);
+        let relative_path = percent_decode_str(relative_path).decode_utf8_lossy();
+        let path = normalize_path(&join_paths(selected_abs_dir, &relative_path, "/"));
+        if !Path::new(&path).is_file() && !missing_files.contains(&path) {
+            missing_files.push(path.clone());
+        }
+        Some(format!("{}{}", encode_url_path(&path), suffix))
+    });
+
+    (css, missing_files)
+}
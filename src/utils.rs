@@ -0,0 +1,91 @@
+use std::path::Path;
+
+pub fn get_trimmed_if_not_empty(str: &str) -> Option<String> {
+    let str = str.trim();
+    if str.is_empty() {None}
+    else {Some(str.to_owned())}
+}
+
+pub fn join_paths(base_absolute_path: &str, relative_path: &str, connective_str: &str) -> String {
+    let concated = format!("{}{}{}", base_absolute_path, connective_str, relative_path);
+    concated.replace("\\", "/").to_owned()
+}
+
+pub fn convert_to_absolute(s: &str) -> String {
+    let p = Path::new(s);
+    if p.is_absolute() {
+        return s.replace("\\", "/");
+    }
+
+    // The "canonicalize" function, (at least on windows) seems to put the weird prefix
+    // "\\?\" before the path and it also puts forward slashes that we want to convert for compatibility.  
+    if let Ok(buf) = std::fs::canonicalize(p) {
+        let str_path = buf.to_str().unwrap();
+        str_path.strip_prefix(r"\\?\").unwrap_or(str_path).replace("\\", "/")
+    } else {
+        s.replace("\\", "/")
+    }
+}
+
+
+// human readable size, like `12.3 KB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+// resolves the `.` and `..` segments of a `/` separated path, without touching the file system
+pub fn normalize_path(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "." => {}
+            "" if !segments.is_empty() => {}
+            ".." => match segments.last() {
+                // already at the root
+                Some(&"") => {}
+                Some(&"..") | None => segments.push(segment),
+                Some(_) => { segments.pop(); }
+            },
+            _ => segments.push(segment),
+        }
+    }
+    if segments == [""] {
+        return "/".to_owned();
+    }
+    segments.join("/")
+}
+
+// the content type of a file with the extension, which is expected in lowercase
+pub fn get_mime_type(extension: &str) -> &'static str {
+    match extension {
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "eot" => "application/vnd.ms-fontobject",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "txt" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,162 @@
+use std::{collections::{BTreeMap, BTreeSet}, path::Path, sync::mpsc, time::{Duration, Instant}};
+
+use anyhow::anyhow;
+use chrono::Local;
+use colored::*;
+use notify::{event::ModifyKind, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use walkdir::WalkDir;
+
+use img_dumper::{history::mark_new_images, references::{SourceReference, SOURCE_EXTENSIONS}, ProjectDir, ScanResult, Scanner};
+
+use crate::{app_config::AppConfig, message_printer::statusln, save_scan_cache};
+
+// a burst of events is handled once, after no event arrived for this long
+const DEBOUNCE_QUIET_TIME: Duration = Duration::from_millis(300);
+
+// a constant stream of events is still handled at least this often
+const DEBOUNCE_MAX_WAIT: Duration = Duration::from_secs(2);
+
+// Watches the root directory and hands a new scan result to `on_rescan` whenever a project changes, until the process is stopped.
+// `traversed_projects` is what `Scanner::traverse_projects` returned for the first report.
+pub fn watch(app_config: &AppConfig, mut traversed_projects: BTreeMap<String, ProjectDir>,
+        mut on_rescan: impl FnMut(ScanResult) -> anyhow::Result<()>) -> anyhow::Result<()> {
+    let scanner = &app_config.scanner;
+    let root_dir = Path::new(scanner.get_root_dir());
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)
+        .map_err(|e| anyhow!(format!("Unable to watch the root directory: {}", e).red()))?;
+    // the folders are watched one by one, since a recursive watch would also go through the ignored ones
+    watch_dir_tree(&mut watcher, root_dir, scanner);
+
+    // the source files of a project are only read again when the project changes, the references of all of them
+    // are still resolved on each change, since they can point at the images of the changed ones
+    let mut source_references: BTreeMap<String, Vec<SourceReference>> = traversed_projects.iter()
+        .map(|(project_name, project_dir)| (project_name.clone(), scanner.read_source_references(project_dir)))
+        .collect();
+
+    statusln!("\nWatching {} for changes, press Ctrl+C to stop", scanner.get_root_dir());
+
+    while let Ok(first_event) = receiver.recv() {
+        let mut changed_project_names = BTreeSet::new();
+        handle_event(first_event, &mut watcher, &mut changed_project_names, &traversed_projects, app_config);
+        let burst_start = Instant::now();
+        while burst_start.elapsed() < DEBOUNCE_MAX_WAIT {
+            match receiver.recv_timeout(DEBOUNCE_QUIET_TIME) {
+                Ok(event) => handle_event(event, &mut watcher, &mut changed_project_names, &traversed_projects, app_config),
+                Err(_) => break,
+            }
+        }
+        if changed_project_names.is_empty() {
+            continue;
+        }
+
+        let instant = Instant::now();
+        let mut changes = Vec::with_capacity(changed_project_names.len());
+        for project_name in &changed_project_names {
+            let image_count_before = get_image_count(&traversed_projects, project_name);
+            scanner.retraverse_project(&mut traversed_projects, project_name);
+            let image_count_after = get_image_count(&traversed_projects, project_name);
+            match traversed_projects.get(project_name) {
+                Some(project_dir) => source_references.insert(project_name.to_owned(), scanner.read_source_references(project_dir)),
+                None => source_references.remove(project_name),
+            };
+            changes.push(match image_count_after as i64 - image_count_before as i64 {
+                0 => project_name.to_owned(),
+                difference => format!("{} ({:+} images)", project_name, difference),
+            });
+        }
+        save_scan_cache(scanner);
+
+        let time = Local::now().format("%H:%M:%S");
+        match rescan(app_config, &traversed_projects, &source_references).and_then(&mut on_rescan) {
+            Ok(()) => statusln!("[{}] {} changed, report updated in {:.2} secs", time, changes.join(", "), instant.elapsed().as_secs_f32()),
+            Err(e) => statusln!("[{}] {} changed, {}", time, changes.join(", "), format!("failed to update the report: {}", e).red()),
+        }
+    }
+
+    Ok(())
+}
+
+// the rest of the scan, after the changed projects were traversed again
+fn rescan(app_config: &AppConfig, traversed_projects: &BTreeMap<String, ProjectDir>,
+        source_references: &BTreeMap<String, Vec<SourceReference>>) -> anyhow::Result<ScanResult> {
+    let scanner = &app_config.scanner;
+    let projects_map = scanner.resolve_projects_with_references(traversed_projects.clone(), source_references);
+    let mut icon_sets = Vec::new();
+    for file_spec in scanner.get_special_file_specs() {
+        if let Some(icon_set) = scanner.parse_special_file(file_spec, &projects_map)? {
+            icon_sets.push(icon_set);
+        }
+    }
+
+    let mut scan_result = scanner.make_result(projects_map, icon_sets);
+    // the images stay new until the next run, which compares them with the snapshot of this one
+    if let Some(previous_snapshot) = &app_config.previous_snapshot {
+        mark_new_images(&mut scan_result, previous_snapshot);
+    }
+
+    Ok(scan_result)
+}
+
+// Adds the project of every path of the event that matters to `changed_project_names`, and starts watching the new folders.
+fn handle_event(event: notify::Result<Event>, watcher: &mut RecommendedWatcher, changed_project_names: &mut BTreeSet<String>,
+        traversed_projects: &BTreeMap<String, ProjectDir>, app_config: &AppConfig) {
+    let Ok(event) = event else { return };
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+
+    for path in &event.paths {
+        let Some(project_name) = get_changed_project_name(path, traversed_projects, app_config) else { continue };
+        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))) && path.is_dir() {
+            watch_dir_tree(watcher, path, &app_config.scanner);
+        }
+        changed_project_names.insert(project_name);
+    }
+}
+
+// The name of the project that the path belongs to, or None when the change doesn't affect the report,
+// like a change inside an ignored folder, in a file that is neither an image nor a source file, or in the output file.
+fn get_changed_project_name(path: &Path, traversed_projects: &BTreeMap<String, ProjectDir>, app_config: &AppConfig) -> Option<String> {
+    let scanner = &app_config.scanner;
+    if path.to_string_lossy().replace('\\', "/") == app_config.output_file_path {
+        return None;
+    }
+    let relative_path = path.strip_prefix(scanner.get_root_dir()).ok()?;
+    let names: Vec<&str> = relative_path.iter().map(|name| name.to_str()).collect::<Option<_>>()?;
+    if names.is_empty() || names.iter().any(|name| scanner.is_ignored_name(name)) {
+        return None;
+    }
+
+    let project_name = names[0];
+    if names.len() == 1 {
+        // a project folder that was added or removed, the files of the root directory are not part of any project
+        return (path.is_dir() || traversed_projects.contains_key(project_name)).then(|| project_name.to_owned());
+    }
+
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default().to_lowercase();
+    let is_relevant = extension.is_empty()
+        || scanner.get_extensions().contains(&extension)
+        || SOURCE_EXTENSIONS.contains(&extension.as_str())
+        || scanner.get_special_file_specs().iter().any(|file_spec| file_spec.extension == extension);
+
+    is_relevant.then(|| project_name.to_owned())
+}
+
+fn watch_dir_tree(watcher: &mut RecommendedWatcher, dir: &Path, scanner: &Scanner) {
+    for entry in WalkDir::new(dir)
+            .follow_links(true)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || (e.file_type().is_dir() && e.file_name().to_str().is_some_and(|name| !scanner.is_ignored_name(name)))) {
+        let Ok(entry) = entry else { continue };
+        if entry.file_type().is_dir() {
+            // a folder that was removed in the meantime is not an error
+            let _ = watcher.watch(entry.path(), RecursiveMode::NonRecursive);
+        }
+    }
+}
+
+fn get_image_count(projects: &BTreeMap<String, ProjectDir>, project_name: &str) -> usize {
+    projects.get(project_name).map(|project_dir| project_dir.images.len()).unwrap_or(0)
+}
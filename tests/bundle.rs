@@ -0,0 +1,80 @@
+use chrono::Local;
+use percent_encoding::percent_decode_str;
+use scraper::{Html, Selector};
+
+use img_dumper::{bundle::BundleAssets, html_report::generate_bundled_html_page_as_string, Img, ProjectDir, ScanResult};
+
+const HOSTILE_NAMES: &[&str] = &[
+    "it's",
+    "<b>",
+    "\"quoted\"",
+    "a&b",
+    "'><img src=x onerror=alert(1)>",
+    "with  two spaces",
+    "hash#tag",
+    "what?x=1",
+    "100%",
+    "ünïcødé",
+    "back\\slash",
+];
+
+const HOSTILE_PROJECT_NAME: &str = "proj'<i>\"&";
+
+// A scan of the projects, each with an image for every file name
+fn make_scan_result(projects: &[(&str, &[&str])]) -> ScanResult {
+    let projects = projects.iter()
+        .map(|&(project_name, file_names)| {
+            let project_path = format!("/root dir/{}", project_name);
+            let images = file_names.iter()
+                .map(|file_name| Img {
+                    name: file_name.to_string(),
+                    path: format!("{}/{}", project_path, file_name),
+                    extension: "png".to_owned(),
+                    ..Img::default()
+                })
+                .collect();
+            (project_name.to_owned(), ProjectDir { name: project_name.to_owned(), path: project_path, images, ..ProjectDir::default() })
+        })
+        .collect();
+
+    ScanResult {
+        scanned_at: Local::now(),
+        root_dir: "/root dir".to_owned(),
+        extensions: vec!["png".to_owned()],
+        projects,
+        duplicates: Vec::new(),
+        similar_clusters: Vec::new(),
+        new_since: None,
+        icon_sets: Vec::new(),
+    }
+}
+
+#[test]
+fn hostile_file_names_round_trip_through_the_bundle_links() {
+    let file_names: Vec<String> = HOSTILE_NAMES.iter().map(|name| format!("{}.png", name)).collect();
+    let file_names: Vec<&str> = file_names.iter().map(|name| name.as_str()).collect();
+    let scan_result = make_scan_result(&[(HOSTILE_PROJECT_NAME, &file_names)]);
+    let assets = BundleAssets::plan(&scan_result);
+    let document = Html::parse_document(&generate_bundled_html_page_as_string(&scan_result, &assets.paths).unwrap());
+    assert_eq!(document.select(&Selector::parse("script").unwrap()).count(), 1);
+
+    let srcs: Vec<String> = document.select(&Selector::parse("li.image-container img").unwrap())
+        .map(|img| percent_decode_str(img.value().attr("src").unwrap()).decode_utf8().unwrap().into_owned())
+        .collect();
+    let project = &scan_result.projects[HOSTILE_PROJECT_NAME];
+    assert_eq!(srcs.len(), project.images.len());
+    for (src, image) in srcs.iter().zip(&project.images) {
+        assert_eq!(Some(src), assets.paths.get(&image.path));
+        assert!(src.starts_with(&format!("assets/images/{}/", HOSTILE_PROJECT_NAME)), "not in the project directory: {}", src);
+    }
+}
+
+#[test]
+fn bundle_assets_with_the_same_name_get_a_suffix() {
+    let scan_result = make_scan_result(&[("Logo", &["icon.png"]), ("logo", &["icon.png"])]);
+
+    let assets = BundleAssets::plan(&scan_result);
+    let paths: Vec<&str> = assets.paths.values().map(|path| path.as_str()).collect();
+    // the second one would overwrite the first on a case insensitive file system
+    assert_eq!(paths, vec!["assets/images/Logo/icon.png", "assets/images/logo/icon-2.png"]);
+}
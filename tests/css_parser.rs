@@ -0,0 +1,90 @@
+use std::{fs::{self, File}, io::BufReader};
+
+use img_dumper::{css_parser::{parse_style_rules, tokenize, Token}, portable::get_css_file_paths, special_files::{parse_css_file, Glyph, ParsedStylesheet}, ParsableFileSpec};
+
+fn get_selectors(css: &str) -> Vec<Vec<Token>> {
+    parse_style_rules(css).into_iter().flat_map(|rule| rule.selectors).collect()
+}
+
+fn class_before(class_name: &str, colon_count: usize) -> Vec<Token> {
+    let mut tokens = vec![Token::Delim('.'), Token::Ident(class_name.to_owned())];
+    tokens.extend(std::iter::repeat_n(Token::Colon, colon_count));
+    tokens.push(Token::Ident("before".to_owned()));
+    tokens
+}
+
+#[test]
+fn minified_and_formatted_stylesheets_have_the_same_rules() {
+    let formatted = "
+        /* .commented:before { content: \"x\"; } */
+        .a:before,
+        .b::before {
+            content: \"\\f101\";
+        }
+
+        @media print {
+            .c:before { content: '}{' !important; }
+        }
+    ";
+    let minified = "/*.commented:before{content:\"x\"}*/.a:before,.b::before{content:\"\\f101\"}@media print{.c:before{content:'}{'!important}}";
+
+    for css in [formatted, minified] {
+        let rules = parse_style_rules(css);
+        assert_eq!(get_selectors(css), vec![class_before("a", 1), class_before("b", 2), class_before("c", 1)]);
+        assert_eq!(rules[0].declarations[0].name, "content");
+        assert_eq!(rules[0].declarations[0].value, vec![Token::String("\u{f101}".to_owned())]);
+        assert_eq!(rules[1].declarations[0].value, vec![Token::String("}{".to_owned())]);
+    }
+}
+
+#[test]
+fn escapes_urls_and_at_rules_are_tokenized() {
+    assert_eq!(tokenize(".\\31 0x\\:y"), vec![Token::Delim('.'), Token::Ident("10x:y".to_owned())]);
+    assert_eq!(tokenize("url( ../a b.woff?v=1#x )"), vec![Token::Url("../a b.woff?v=1#x".to_owned())]);
+    assert_eq!(tokenize("url('a.woff')"), vec![Token::Function("url".to_owned()), Token::String("a.woff".to_owned()), Token::CloseParen]);
+
+    // the rules of `@font-face` and `@import` are not style rules, a missing brace ends with the stylesheet
+    let css = "@import url(x.css);@font-face{src:url(a.woff)}.a:before{content:'a'";
+    assert_eq!(get_selectors(css), vec![class_before("a", 1)]);
+}
+
+fn parse_file(name: &str, css: &str) -> ParsedStylesheet {
+    let path = std::env::temp_dir().join(format!("img-dumper-test-{}.css", name));
+    fs::write(&path, css).unwrap();
+    let file_spec = ParsableFileSpec::new(name, name, "css", None, None, css.len(), parse_css_file);
+    let parsed = parse_css_file(&file_spec, "/css", BufReader::new(File::open(&path).unwrap())).unwrap();
+    fs::remove_file(&path).unwrap();
+    parsed
+}
+
+#[test]
+fn font_awesome_6_icons_are_drawn_with_the_family_of_their_section() {
+    let parsed = parse_file("fa6", ".fa-brands,.fab{font-family:\"Font Awesome 6 Brands\"}.fa-bars,.fa-navicon{--fa:\"\\f0c9\"}\
+        .fa-brands,.fab{font-weight:400}.fa-github{--fa:\"\\f09b\"}.fa-solid,.fas{font-weight:900}");
+
+    assert_eq!(parsed.glyphs, vec![
+        Glyph { class_names: vec!["fa-bars".to_owned(), "fa-navicon".to_owned()], codepoint: Some(0xf0c9), family: None },
+        Glyph { class_names: vec!["fa-github".to_owned()], codepoint: Some(0xf09b), family: Some("fa-brands".to_owned()) },
+    ]);
+    assert_eq!(parsed.style_families, vec!["fa-solid", "fa-brands"]);
+    assert_eq!(parsed.base_class.as_deref(), Some("fa-solid"));
+
+    let parsed = parse_file("bi", ".bi::before,[class^=\"bi-\"]::before{font-family:bootstrap-icons!important;content:\"\"}.bi-alarm::before{content:\"\\f102\"}");
+    assert_eq!(parsed.glyphs, vec![Glyph { class_names: vec!["bi-alarm".to_owned()], codepoint: Some(0xf102), family: None }]);
+    assert_eq!(parsed.base_class.as_deref(), Some("bi"));
+    assert!(parsed.style_families.is_empty());
+}
+
+#[test]
+fn relative_urls_of_every_quoting_become_absolute_and_the_others_are_kept() {
+    let parsed = parse_file("urls", "@font-face{src:url(../fonts/a.eot?v=4.7.0#iefix) format('embedded-opentype'),\
+        url( '../fonts/b c.woff2' ),URL(\"./x/../d\\\"q.woff\"),url(data:font/woff;base64,AA==),url(https://cdn/e.woff),\
+        url(//cdn/f.woff),url(/static/g.woff),url(C:/fonts/h.woff)}.a:before{content:'url(i.woff)';filter:url(#clip)}/* url(j.woff) */");
+
+    assert_eq!(parsed.css, "@font-face{src:url(\"/fonts/a.eot?v=4.7.0#iefix\") format('embedded-opentype'),\
+        url(\"/fonts/b%20c.woff2\"),url(\"/css/d%22q.woff\"),url(data:font/woff;base64,AA==),url(https://cdn/e.woff),\
+        url(//cdn/f.woff),url(/static/g.woff),url(C:/fonts/h.woff)}.a:before{content:'url(i.woff)';filter:url(#clip)}/* url(j.woff) */");
+    assert_eq!(parsed.missing_files, vec!["/fonts/a.eot", "/fonts/b c.woff2", "/css/d\"q.woff"]);
+    // the portable report and the bundle find the files back, without their query
+    assert_eq!(get_css_file_paths(&parsed.css), vec!["/fonts/a.eot", "/fonts/b c.woff2", "/css/d\"q.woff", "/static/g.woff"]);
+}
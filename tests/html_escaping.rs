@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+
+use chrono::Local;
+use percent_encoding::percent_decode_str;
+use scraper::{Html, Selector};
+
+use img_dumper::{diff_report::generate_diff_html_page_as_string,
+    html_report::generate_html_page_as_string, references::SourceReference,
+    scan_diff::{diff_snapshots, Snapshot}, special_files::Glyph, IconSet, Img, ProjectDir, ScanResult};
+
+const HOSTILE_NAMES: &[&str] = &[
+    "it's",
+    "<b>",
+    "\"quoted\"",
+    "a&b",
+    "a&amp;b",
+    "</script><script>alert(1)</script>",
+    "'><img src=x onerror=alert(1)>",
+    "\"><svg onload=alert(1)>",
+    "--><!--",
+    "with  two spaces",
+    "hash#tag",
+    "what?x=1",
+    "100%",
+    "ünïcødé",
+    "日本語",
+    "back\\slash",
+];
+
+const HOSTILE_PROJECT_NAME: &str = "proj'<i>\"&";
+const HOSTILE_EXTENSION: &str = "</script><script>alert('ext')</script>";
+
+fn make_scan_result() -> ScanResult {
+    let project_path = format!("/root dir/{}", HOSTILE_PROJECT_NAME);
+    let images = HOSTILE_NAMES.iter()
+        .map(|name| Img {
+            name: name.to_string(),
+            path: format!("{}/{}.png", project_path, name),
+            extension: "png".to_owned(),
+            // embedded in the script, for the details panel
+            references: Some(vec![SourceReference {
+                source_path: format!("{}/{}.html", project_path, name),
+                line: 1,
+                target: format!("{}.png", name),
+                snippet: format!("<img src=\"{}.png\">", name),
+                is_partial: false,
+            }]),
+            ..Img::default()
+        })
+        .collect();
+
+    let mut projects = BTreeMap::new();
+    projects.insert(HOSTILE_PROJECT_NAME.to_owned(), ProjectDir {
+        name: HOSTILE_PROJECT_NAME.to_owned(),
+        path: project_path,
+        images,
+        source_files: Vec::new(),
+        broken_references: Vec::new(),
+    });
+
+    ScanResult {
+        scanned_at: Local::now(),
+        root_dir: "/root dir".to_owned(),
+        extensions: vec!["png".to_owned(), HOSTILE_EXTENSION.to_owned()],
+        projects,
+        duplicates: Vec::new(),
+        similar_clusters: Vec::new(),
+        new_since: None,
+        icon_sets: vec![IconSet {
+            title: "icons */ </style>".to_owned(),
+            source_dir: "/fonts/<x>".to_owned(),
+            base_class: "fa' onclick='alert(1)".to_owned(),
+            class_prefix: "fa-".to_owned(),
+            class_names: vec!["fa-<b>".to_owned(), "fa-it's".to_owned()],
+            glyphs: vec![
+                Glyph { class_names: vec!["fa-<b>".to_owned(), "fa-'alias".to_owned()], codepoint: Some(0xf0c9), family: None },
+                Glyph { class_names: vec!["fa-it's".to_owned()], codepoint: None, family: Some("fa-brands' onclick='alert(2)".to_owned()) },
+            ],
+            style_families: vec!["fa-brands' onclick='alert(2)".to_owned()],
+            css: ".fa-x:before { content: \"</style><script>alert(1)</script>\"; }\n".to_owned(),
+            missing_files: Vec::new(),
+        }],
+    }
+}
+
+fn render() -> Html {
+    let html = generate_html_page_as_string(&make_scan_result()).unwrap();
+    Html::parse_document(&html)
+}
+
+fn select<'a>(document: &'a Html, selector: &str) -> Vec<scraper::ElementRef<'a>> {
+    document.select(&Selector::parse(selector).unwrap()).collect()
+}
+
+#[test]
+fn hostile_file_names_do_not_inject_elements_or_attributes() {
+    let document = render();
+
+    assert_eq!(select(&document, "script").len(), 1);
+    assert_eq!(select(&document, "style").len(), 1);
+    assert!(select(&document, "[onerror], [onload]").is_empty());
+    assert!(select(&document, "[onclick]").iter().all(|e| e.value().attr("onclick") == Some("toggleProjectArea(event)")));
+    assert!(select(&document, "b, svg").is_empty());
+}
+
+#[test]
+fn hostile_file_names_round_trip_through_text_and_attributes() {
+    let document = render();
+    let scan_result = make_scan_result();
+    let project = scan_result.projects.values().next().unwrap();
+
+    let lis = select(&document, "li.image-container[data-path]");
+    assert_eq!(lis.len(), project.images.len());
+
+    for (li, image) in lis.iter().zip(&project.images) {
+        assert_eq!(li.value().attr("data-path"), Some(image.path.as_str()));
+        assert!(li.value().attr("title").unwrap().starts_with(&image.path));
+
+        let name_span = li.select(&Selector::parse(":scope > span").unwrap()).last().unwrap();
+        assert_eq!(name_span.text().collect::<String>(), image.name);
+
+        let src = li.select(&Selector::parse("img").unwrap()).next().unwrap().value().attr("src").unwrap();
+        assert!(!src.contains([' ', '#', '?', '"', '\'']), "unencoded src: {}", src);
+        assert!(src.is_ascii(), "unencoded src: {}", src);
+        assert_eq!(percent_decode_str(src).decode_utf8().unwrap().replace('\\', "/"), image.path.replace('\\', "/"));
+    }
+
+    let titles: Vec<String> = select(&document, "h1.title").iter().map(|h1| h1.text().collect()).collect();
+    assert!(titles.contains(&HOSTILE_PROJECT_NAME.to_owned()));
+}
+
+#[test]
+fn hostile_extensions_and_icon_classes_round_trip() {
+    let document = render();
+
+    let checkbox_values: Vec<&str> = select(&document, "input[type=checkbox]").iter()
+        .filter_map(|input| input.value().attr("value"))
+        .collect();
+    assert_eq!(checkbox_values, vec!["png", HOSTILE_EXTENSION]);
+
+    let icon_classes: Vec<&str> = select(&document, "i").iter()
+        .filter_map(|i| i.value().attr("class"))
+        .collect();
+    assert_eq!(icon_classes, vec!["fa' onclick='alert(1) fa-<b>", "fa-brands' onclick='alert(2) fa-it's"]);
+
+    let glyph_searches: Vec<&str> = select(&document, "li.glyph").iter()
+        .filter_map(|li| li.value().attr("data-search"))
+        .collect();
+    assert_eq!(glyph_searches, vec!["fa-<b> fa-'alias U+f0c9", "fa-it's"]);
+    let copied_values: Vec<&str> = select(&document, "li.glyph [data-copy]").iter()
+        .filter_map(|button| button.value().attr("data-copy"))
+        .collect();
+    assert_eq!(copied_values, vec!["\\f0c9", "&#xf0c9;"]);
+
+    let script = select(&document, "script")[0].text().collect::<String>();
+    assert!(script.contains("currentlySelectedExtensions"));
+    assert!(script.contains("imageReferences"));
+}
+
+#[test]
+fn hostile_file_names_do_not_inject_elements_into_the_diff() {
+    let scan_result = make_scan_result();
+    let mut old = Snapshot::from_scan_result(&scan_result, "old</b><script>alert(1)</script>");
+    old.images.clear();
+    old.icon_sets.clear();
+    let new = Snapshot::from_scan_result(&scan_result, "new");
+    let document = Html::parse_document(&generate_diff_html_page_as_string(&diff_snapshots(old, new)));
+
+    assert!(select(&document, "script").is_empty());
+    assert!(select(&document, "[onerror], [onload], [onclick], svg, i").is_empty());
+    // the labels of the two snapshots
+    assert_eq!(select(&document, "b").len(), 2);
+
+    let paths: Vec<String> = select(&document, "tr.added .change-paths span:first-child").iter()
+        .map(|span| span.text().collect())
+        .collect();
+    let mut expected_paths: Vec<String> = HOSTILE_NAMES.iter().map(|name| format!("{}/{}.png", HOSTILE_PROJECT_NAME, name)).collect();
+    expected_paths.sort();
+    assert_eq!(paths, expected_paths);
+
+    let class_names: Vec<String> = select(&document, "ul.class-names li").iter().map(|li| li.text().collect()).collect();
+    assert_eq!(class_names, vec!["+ fa-<b>", "+ fa-it's"]);
+}